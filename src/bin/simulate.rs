@@ -0,0 +1,42 @@
+use hcor::simulate::{simulate, SimulationOptions};
+use hcor::CONFIG;
+use std::time::Duration;
+
+const USAGE: &'static str = concat!(
+    "usage: simulate <plant name> [--for <duration>] [--sample-every <duration>]\n",
+    "  --for <duration>           how long to grow the plant for, i.e. \"3days\" (default 1week)\n",
+    "  --sample-every <duration>  how often to record the plant's xp (default 1h)",
+);
+
+fn parse_duration(arg: Option<String>) -> Result<Duration, String> {
+    let arg = arg.ok_or_else(|| format!("missing duration\n{}", USAGE))?;
+    humantime::parse_duration(&arg).map_err(|e| format!("invalid duration {:?}: {}", arg, e))
+}
+
+fn main() -> Result<(), String> {
+    let mut opts = SimulationOptions::default();
+    let mut plant_name = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--for" => opts.horizon = parse_duration(args.next())?,
+            "--sample-every" => {
+                let every = parse_duration(args.next())?;
+                opts.xp_sample_interval =
+                    (every.as_millis() / opts.farm_cycle.as_millis()).max(1) as u64;
+            }
+            _ if plant_name.is_none() => plant_name = Some(arg),
+            other => return Err(format!("unknown argument {:?}\n{}", other, USAGE)),
+        }
+    }
+
+    let plant_name = plant_name.ok_or_else(|| USAGE.to_string())?;
+    let plant = CONFIG
+        .find_plant(&plant_name)
+        .map_err(|e| format!("{}", e))?;
+
+    println!("{}", simulate(plant, &opts));
+
+    Ok(())
+}
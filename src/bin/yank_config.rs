@@ -1,7 +1,19 @@
 #![recursion_limit = "256"]
-use hcor::yank_config::{yank_config, YankError};
+use hcor::yank_config::{yank_config, ConfigSummary, YankError, YankOptions};
 
 #[tokio::main]
 async fn main() -> Result<(), YankError> {
-    yank_config().await
+    dotenv::dotenv().ok();
+
+    let opts = YankOptions::from_args(std::env::args().skip(1))?;
+    let config = yank_config(&opts).await?;
+
+    if opts.dry_run {
+        println!("{}", ConfigSummary(&config));
+        println!("dry run, not writing to {}", opts.output_path.display());
+    } else {
+        println!("wrote config to {}", opts.output_path.display());
+    }
+
+    Ok(())
 }
@@ -12,6 +12,12 @@ pub enum Category {
     Misc = 2,
     Land = 3,
     Sale = 9,
+    /// A single change to a Hacksteader's balance.
+    LedgerEntry = 10,
+    /// A record of something being bought on the market, kept for price history.
+    SaleEvent = 11,
+    /// An offer for two Hacksteaders to trade with each other.
+    TradeOffer = 12,
 }
 
 impl fmt::Display for Category {
@@ -80,6 +86,9 @@ impl std::convert::TryFrom<u8> for Category {
             2 => Misc,
             3 => Land,
             9 => Sale,
+            10 => LedgerEntry,
+            11 => SaleEvent,
+            12 => TradeOffer,
             _ => return Err(CategoryError::UnknownCategory),
         })
     }
@@ -2,15 +2,17 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum ConfigError {
     UnknownArchetypeName(String),
+    UnknownMarket(String),
 }
 impl fmt::Display for ConfigError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use ConfigError::*;
         match self {
             UnknownArchetypeName(name) => write!(f, "no archetype by the name of {:?}", name),
+            UnknownMarket(name) => write!(f, "no market by the name of {:?}", name),
         }
     }
 }
@@ -24,6 +26,45 @@ pub struct Config {
     pub profile_archetype: ProfileArchetype,
     pub plant_archetypes: Vec<PlantArchetype>,
     pub possession_archetypes: Vec<Archetype>,
+    #[serde(default)]
+    pub markets: Vec<Market>,
+}
+
+/// A market possessions can be listed on, and the rules listings there have to follow.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Market {
+    pub name: String,
+    /// The kinds of possessions which can be sold here; if empty, anything can be.
+    #[serde(default)]
+    pub kinds: Vec<crate::market::KindFilter>,
+    /// How much gp it costs to put something up for sale here.
+    #[serde(default)]
+    pub listing_fee: u64,
+    /// What percent of each sale's price this market keeps for itself.
+    #[serde(default)]
+    pub sales_tax_percent: u64,
+    #[serde(default)]
+    pub min_price: u64,
+    #[serde(default)]
+    pub max_price: Option<u64>,
+}
+impl Market {
+    /// Makes sure this market's rules make sense, so that listings on it can be priced and taxed.
+    pub fn check(&self) -> Result<(), String> {
+        if self.sales_tax_percent > 100 {
+            return Err(format!(
+                "market {:?} takes {}% of each sale, but can't take more than 100%",
+                self.name, self.sales_tax_percent
+            ));
+        }
+        match self.max_price {
+            Some(max) if max < self.min_price => Err(format!(
+                "market {:?} has a max price of {}gp, below its min price of {}gp",
+                self.name, max, self.min_price
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 pub trait SpawnTableRow {
@@ -40,7 +81,7 @@ pub trait SpawnTableRow {
     fn count(&self, rng: &mut impl rand::RngCore) -> usize;
 
     /// What does this row actually produce?
-    fn output(&self, index: usize) -> Self::Output;
+    fn output(&self, index: usize, rng: &mut impl rand::RngCore) -> Self::Output;
 }
 
 impl<Handle: Clone> SpawnTableRow for (CountProbability, Handle) {
@@ -52,7 +93,7 @@ impl<Handle: Clone> SpawnTableRow for (CountProbability, Handle) {
     fn count(&self, rng: &mut impl rand::RngCore) -> usize {
         self.0.gen_count(rng)
     }
-    fn output(&self, _index: usize) -> Self::Output {
+    fn output(&self, _index: usize, _rng: &mut impl rand::RngCore) -> Self::Output {
         self.1.clone()
     }
 }
@@ -62,9 +103,10 @@ pub fn spawn<'rng, Row: SpawnTableRow>(
     table: &'rng Vec<Row>,
     rng: &'rng mut impl rand::RngCore,
 ) -> impl Iterator<Item = Row::Output> + 'rng {
-    table
-        .iter()
-        .flat_map(move |row| (0..row.count(rng)).map(move |i| row.output(i)))
+    table.iter().flat_map(move |row| {
+        let count = row.count(rng);
+        (0..count).map(|i| row.output(i, rng)).collect::<Vec<_>>()
+    })
 }
 
 /// Quite similar to spawn(), but it also returns a list of the
@@ -82,7 +124,7 @@ pub fn spawn_with_percentile<Row: SpawnTableRow>(
                 None
             } else {
                 Some((
-                    (0..count).map(move |i| row.output(i)).collect::<Vec<_>>(),
+                    (0..count).map(|i| row.output(i, rng)).collect::<Vec<_>>(),
                     row.rarity(),
                 ))
             }
@@ -135,6 +177,23 @@ impl Config {
             .ok_or(ConfigError::UnknownArchetypeName(name.as_ref().to_string()))
     }
 
+    /// Finds the seed which grows into the plant with the given name.
+    pub fn find_seed_handle<S: AsRef<str>>(
+        &self,
+        plant_name: &S,
+    ) -> Result<ArchetypeHandle, ConfigError> {
+        self.possession_archetypes
+            .iter()
+            .position(|x| match &x.kind {
+                ArchetypeKind::Seed(s) => s.grows_into == plant_name.as_ref(),
+                _ => false,
+            })
+            .ok_or(ConfigError::UnknownArchetypeName(format!(
+                "{} Seed",
+                plant_name.as_ref()
+            )))
+    }
+
     pub fn find_plant_handle<S: AsRef<str>>(
         &self,
         name: &S,
@@ -152,6 +211,13 @@ impl Config {
             .ok_or(ConfigError::UnknownArchetypeName(name.as_ref().to_string()))
     }
 
+    pub fn find_market<S: AsRef<str>>(&self, name: &S) -> Result<&Market, ConfigError> {
+        self.markets
+            .iter()
+            .find(|x| name.as_ref() == x.name)
+            .ok_or(ConfigError::UnknownMarket(name.as_ref().to_string()))
+    }
+
     pub fn find_possession_handle<S: AsRef<str>>(
         &self,
         name: &S,
@@ -181,10 +247,19 @@ lazy_static::lazy_static! {
             .unwrap_or_else(|e| panic!("parsing {}: {}", p, e))
         }
 
-        Config {
-            special_users: f("special_users"),
-            ..f("content")
+        // special users can come from the yanked content as well as being hand-picked
+        let mut config: Config = f("content");
+        for user in f::<Vec<String>>("special_users") {
+            if !config.special_users.contains(&user) {
+                config.special_users.push(user);
+            }
+        }
+        // markets aren't yanked, they're all hand-picked
+        config.markets.extend(f::<Vec<Market>>("markets"));
+        for market in &config.markets {
+            market.check().unwrap_or_else(|e| panic!("{}", e));
         }
+        config
     };
 }
 
@@ -276,12 +351,21 @@ pub struct SelectivePlantAdvancement {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GotchiArchetype {
     pub base_happiness: u64,
+    /// The items this gotchi can be fed, and how much happier each one makes it.
+    #[serde(default)]
+    pub foods: Vec<(String, u64)>,
+    /// How much of the happiness this gotchi gains above its base happiness wears off each hour.
+    #[serde(default = "default_happiness_decay")]
+    pub happiness_decay: u64,
     #[serde(default)]
     pub plant_effects: Vec<SelectivePlantAdvancement>,
     pub hatch_table: Option<LootTable>,
     #[serde(default)]
     pub welcome_gift: bool,
 }
+fn default_happiness_decay() -> u64 {
+    1
+}
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SeedArchetype {
     pub grows_into: String,
@@ -301,11 +385,29 @@ pub enum ItemApplicationEffectKind {
     PlantAdvancement(PlantAdvancement),
     TurnsPlantInto(String),
 }
+/// What happens when an effect is applied to a plant which already has it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum EffectStacking {
+    /// Both effects apply, each wearing off on their own.
+    Stack,
+    /// The existing effect lasts as long as it would have if it had just been applied.
+    Refresh,
+    /// The existing effect is removed in favor of the new one.
+    Replace,
+}
+impl Default for EffectStacking {
+    fn default() -> Self {
+        EffectStacking::Stack
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ItemApplicationEffect {
     pub duration: Option<f32>,
     pub keep_plants: KeepPlants<String>,
     pub kind: ItemApplicationEffectKind,
+    #[serde(default)]
+    pub stacking: EffectStacking,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -378,9 +480,8 @@ pub enum RecipeMakes<Handle: Clone> {
     AllOf(Vec<(usize, Handle)>),
 }
 impl<Handle: Clone> RecipeMakes<Handle> {
-    fn pick_one_weighted_of<T: Clone>(from: &Vec<(f32, T)>) -> T {
-        use rand::Rng;
-        let mut x: f32 = rand::thread_rng().gen_range(0.0, 1.0);
+    fn pick_one_weighted_of<T: Clone>(from: &Vec<(f32, T)>, rng: &mut impl rand::Rng) -> T {
+        let mut x: f32 = rng.gen_range(0.0, 1.0);
         from.iter()
             .find_map(|(chance, h)| {
                 x -= chance;
@@ -396,11 +497,11 @@ impl<Handle: Clone> RecipeMakes<Handle> {
 
     /// Returns one possible output, randomly (but properly weighted)
     /// if more than one is possible.
-    pub fn any(&self) -> Option<Handle> {
+    pub fn any(&self, rng: &mut impl rand::Rng) -> Option<Handle> {
         use RecipeMakes::*;
 
         match self {
-            OneOf(these) => Self::pick_one_weighted_of(these).any(),
+            OneOf(these) => Self::pick_one_weighted_of(these, rng).any(rng),
             Just(_, h) => Some(h.clone()),
             AllOf(these) => {
                 let total = these.iter().map(|(count, _)| *count).sum::<usize>() as f32;
@@ -409,40 +510,54 @@ impl<Handle: Clone> RecipeMakes<Handle> {
                         .iter()
                         .map(|(count, h)| (*count as f32 / total, h.clone()))
                         .collect(),
+                    rng,
                 ))
             }
             Nothing => return None,
         }
     }
 
-    /// A list of everything that could possibly come from this recipe
-    pub fn all(&self) -> Vec<(Handle, usize)> {
+    /// A list of everything that could come from this recipe,
+    /// after choosing one of the possibilities for any OneOfs.
+    pub fn all(&self, rng: &mut impl rand::Rng) -> Vec<(Handle, usize)> {
         use RecipeMakes::*;
 
         match self {
-            OneOf(these) => Self::pick_one_weighted_of(these).all(),
+            OneOf(these) => Self::pick_one_weighted_of(these, rng).all(rng),
             Just(count, h) => [(h.clone(), *count)].iter().cloned().collect(),
             AllOf(these) => these.iter().map(|(count, h)| (h.clone(), *count)).collect(),
             Nothing => vec![],
         }
     }
 
+    /// Every handle this recipe could ever output, no matter the roll.
+    pub fn possibilities(&self) -> Vec<&Handle> {
+        use RecipeMakes::*;
+
+        match self {
+            OneOf(these) => these.iter().flat_map(|(_, r)| r.possibilities()).collect(),
+            Just(_, h) => vec![h],
+            AllOf(these) => these.iter().map(|(_, h)| h).collect(),
+            Nothing => vec![],
+        }
+    }
+
     /// A proper output given the constraints of this recipe;
     /// for example, OneOfWeighted will always return one of the possibilities,
     /// properly weighted.
-    pub fn output(self) -> Vec<Handle> {
+    pub fn output(self, rng: &mut impl rand::Rng) -> Vec<Handle> {
         use RecipeMakes::*;
 
         match &self {
-            OneOf(these) => Self::pick_one_weighted_of(these).output(),
+            OneOf(these) => Self::pick_one_weighted_of(these, rng).output(rng),
             Just(count, _) => (0..*count)
                 .map(|_| {
-                    self.any()
+                    self.any(rng)
                         .expect("RecipeMakes::Just.any() can't return None")
                 })
                 .collect(),
             AllOf(_) => self
-                .all()
+                .all(rng)
                 .into_iter()
                 .flat_map(|(what, count)| (0..count).map(move |_| what.clone()))
                 .collect(),
@@ -538,6 +653,31 @@ impl RecipeMakes<String> {
     }
 }
 
+#[test]
+fn seeded_recipe_output() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let makes = RecipeMakes::OneOf(vec![
+        (0.5, RecipeMakes::Just(2, "Bread Essence")),
+        (
+            0.5,
+            RecipeMakes::AllOf(vec![(1, "Cyl Crystal"), (3, "Hacker Spirit")]),
+        ),
+    ]);
+    let outputs = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..20)
+            .map(|_| (makes.clone().output(&mut rng), makes.any(&mut rng)))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(outputs(7), outputs(7));
+    assert_eq!(
+        makes.possibilities(),
+        vec![&"Bread Essence", &"Cyl Crystal", &"Hacker Spirit"]
+    );
+}
+
 /// Recipe is generic over the way Archetypes are referred to
 /// to make it easy to use Strings in the configs and ArchetypeHandles
 /// at runtime
@@ -587,24 +727,52 @@ impl Recipe<ArchetypeHandle> {
     }
 }
 impl Recipe<&Archetype> {
+    /// Unless the recipe is given a title, it's named after the first thing it could make.
     pub fn title(&self) -> String {
         self.title.clone().unwrap_or_else(|| {
             self.makes
-                .any()
+                .possibilities()
+                .first()
                 .map(|e| format!("{} {}", crate::frontend::emojify(&e.name), e.name))
                 .unwrap_or("Nothing".to_string())
         })
     }
+    /// Unless the recipe is given an explanation, it's explained by the description of
+    /// the first thing it could make.
     pub fn explanation(&self) -> String {
         self.explanation.clone().unwrap_or_else(|| {
             self.makes
-                .any()
+                .possibilities()
+                .first()
                 .map(|e| e.description.clone())
                 .unwrap_or("Nothing".to_string())
         })
     }
 }
 
+#[test]
+fn untitled_recipes_name_their_first_output() {
+    let archetype = |name| CONFIG.find_possession(&name).unwrap();
+    let (essence, crystal) = (archetype("Bread Essence"), archetype("Cyl Crystal"));
+    let recipe = Recipe {
+        title: None,
+        explanation: None,
+        needs: vec![],
+        makes: RecipeMakes::OneOf(vec![
+            (0.5, RecipeMakes::Just(1, essence)),
+            (0.5, RecipeMakes::Just(1, crystal)),
+        ]),
+        destroys_plant: false,
+        time: 1.0,
+        xp: (0, 0),
+    };
+
+    for _ in 0..10 {
+        assert!(recipe.title().ends_with("Bread Essence"));
+        assert_eq!(recipe.explanation(), essence.description);
+    }
+}
+
 /// a number between these two bounds is chosen (the first is the lower bound, the second is
 /// the higher bound). The floating point number is then split into its fractional and integral
 /// counterparts. The integral counterpart is the base number of items to award, and the
@@ -618,7 +786,8 @@ impl CountProbability {
     pub fn gen_count<R: rand::Rng>(self, rng: &mut R) -> usize {
         let Self(guard, (lo, hi)) = self;
         if rng.gen_range(0.0, 1.0) < guard {
-            let chance = rng.gen_range(lo, hi);
+            // an empty range can't be rolled, so it always lands on its lower bound
+            let chance = if hi > lo { rng.gen_range(lo, hi) } else { lo };
             let base = chance.floor();
             let extra = if rng.gen_range(0.0, 1.0) < chance - base {
                 1
@@ -630,6 +799,85 @@ impl CountProbability {
             0
         }
     }
+
+    /// The average number of items gen_count would return, were it called many times.
+    pub fn expected_count(self) -> f32 {
+        let Self(guard, (lo, hi)) = self;
+        guard * (lo + hi) / 2.0
+    }
+
+    /// The chance gen_count has of returning each count,
+    /// i.e. the number at index 2 is the probability of getting 2 items.
+    pub fn count_distribution(self) -> Vec<f32> {
+        let Self(guard, (lo, hi)) = self;
+        let mut odds = vec![0.0; hi.max(lo).floor() as usize + 2];
+
+        if hi <= lo {
+            let base = lo.floor();
+            odds[base as usize] += 1.0 - (lo - base);
+            odds[base as usize + 1] += lo - base;
+        } else {
+            // the amount is picked uniformly from [lo, hi), so each whole number in that range
+            // contributes its base count, and then an extra item with a chance that
+            // grows linearly with how far past the whole number the amount landed.
+            let mut start = lo;
+            while start < hi {
+                let base = start.floor();
+                let end = hi.min(base + 1.0);
+                let width = (end - start) / (hi - lo);
+                let extra = (end - base + start - base) / 2.0;
+
+                odds[base as usize] += width * (1.0 - extra);
+                odds[base as usize + 1] += width * extra;
+                start = end;
+            }
+        }
+
+        for chance in odds.iter_mut() {
+            *chance *= guard;
+        }
+        odds[0] += 1.0 - guard;
+
+        while odds.len() > 1 && odds.last() == Some(&0.0) {
+            odds.pop();
+        }
+        odds
+    }
+}
+
+#[test]
+fn count_distribution_matches_expected_count() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    for &cp in &[
+        CountProbability(1.0, (1.4, 1.6)),
+        CountProbability(0.09, (2.5, 3.8)),
+        CountProbability(0.5, (2.0, 2.0)),
+        CountProbability(1.0, (0.0, 4.0)),
+    ] {
+        let odds = cp.count_distribution();
+        let total: f32 = odds.iter().sum();
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..100 {
+            let count = cp.gen_count(&mut rng);
+            assert!(
+                odds.get(count).map_or(false, |&p| p > 0.0),
+                "{:?} rolled {}",
+                cp,
+                count
+            );
+        }
+        let mean: f32 = odds.iter().enumerate().map(|(n, p)| n as f32 * p).sum();
+
+        assert!((total - 1.0).abs() < 0.0001, "{:?} sums to {}", cp, total);
+        assert!(
+            (mean - cp.expected_count()).abs() < 0.0001,
+            "{:?} has a mean of {} but expects {}",
+            cp,
+            mean,
+            cp.expected_count()
+        );
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -687,6 +935,41 @@ impl Yield<String> {
     }
 }
 
+#[test]
+fn seeded_spawn() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let table: Vec<Yield<&str>> = vec![
+        Yield {
+            chance: 0.8,
+            amount: (1.0, 3.5),
+            dropoff: 1.5,
+            xp: (10, 20),
+            yields: "Bread Essence",
+        },
+        Yield {
+            chance: 0.1,
+            amount: (2.5, 3.8),
+            dropoff: 1.0,
+            xp: (5, 50),
+            yields: "Bractus Seed",
+        },
+    ];
+    let spawned = |seed| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..50)
+            .map(|_| {
+                (
+                    spawn(&table, &mut rng).collect::<Vec<_>>(),
+                    spawn_with_percentile(&table, &mut rng),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(spawned(42), spawned(42));
+}
+
 impl<Handle: Clone> SpawnTableRow for Yield<Handle> {
     /// Yields also return a certain amount of xp,
     /// alongside something you can use to spawn an item with.
@@ -698,13 +981,13 @@ impl<Handle: Clone> SpawnTableRow for Yield<Handle> {
     fn count(&self, rng: &mut impl rand::RngCore) -> usize {
         CountProbability(self.chance, self.amount).gen_count(rng)
     }
-    fn output(&self, index: usize) -> Self::Output {
+    fn output(&self, index: usize, rng: &mut impl rand::RngCore) -> Self::Output {
         (self.yields.clone(), {
             use rand::Rng;
 
             let (lo, hi) = self.xp;
-            (rand::thread_rng().gen_range(lo, hi) as f32 / ((index + 1) as f32 * self.dropoff))
-                .round() as usize
+            let xp = if hi > lo { rng.gen_range(lo, hi) } else { lo };
+            (xp as f32 / ((index + 1) as f32 * self.dropoff)).round() as usize
         })
     }
 }
@@ -725,6 +1008,48 @@ pub enum PlantAdvancementKind {
     CraftSpeedMultiplier(f32),
     CraftReturnChance(f32),
     DoubleCraftYield(f32),
+    /// Whenever the plant yields, it may also produce seeds of its own kind.
+    SeedYield(CountProbability),
+    /// Only applies to plants grown from at least this many generations of seeds.
+    Pedigree(u64, Box<PlantAdvancementKind>),
+}
+
+impl PlantAdvancementKind {
+    /// A weaker or stronger version of this advancement; at a strength of 1.0 nothing changes,
+    /// at 0.0 it has no effect. Yields and recipes can't be scaled, so they stay the same.
+    pub fn scaled(&self, strength: f32) -> Self {
+        use PlantAdvancementKind::*;
+
+        let multiplier = |m: f32| 1.0 + (m - 1.0) * strength;
+        match self {
+            Neighbor(n) => Neighbor(Box::new(n.scaled(strength))),
+            &ExtraTimeTicks(extra) => ExtraTimeTicks((extra as f32 * strength).round() as u32),
+            &TimeTicksMultiplier(m) => TimeTicksMultiplier(multiplier(m)),
+            &Xp(xp) => Xp(xp * strength),
+            &YieldSpeedMultiplier(m) => YieldSpeedMultiplier(multiplier(m)),
+            &YieldSizeMultiplier(m) => YieldSizeMultiplier(multiplier(m)),
+            &CraftSpeedMultiplier(m) => CraftSpeedMultiplier(multiplier(m)),
+            &CraftReturnChance(chance) => CraftReturnChance(chance * strength),
+            &DoubleCraftYield(chance) => DoubleCraftYield(chance * strength),
+            &SeedYield(CountProbability(chance, amount)) => {
+                SeedYield(CountProbability((chance * strength).min(1.0), amount))
+            }
+            Pedigree(generations, p) => Pedigree(*generations, Box::new(p.scaled(strength))),
+            other @ Yield(_) | other @ Craft(_) => other.clone(),
+        }
+    }
+
+    /// What this does for a plant grown from this many generations of seeds,
+    /// or None if it does nothing; only `Pedigree` advancements care.
+    pub fn for_generations(&self, generations: u64) -> Option<&Self> {
+        use PlantAdvancementKind::*;
+
+        match self {
+            Pedigree(needed, _) if generations < *needed => None,
+            Pedigree(_, p) => p.for_generations(generations),
+            other => Some(other),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
@@ -744,6 +1069,8 @@ pub struct PlantAdvancementSum {
     pub crafting_speed_multiplier: f32,
     pub craft_return_chance: f32,
     pub recipes: Vec<Recipe<ArchetypeHandle>>,
+    // seeds
+    pub seed_yields: Vec<CountProbability>,
 }
 impl AdvancementSum for PlantAdvancementSum {
     type Kind = PlantAdvancementKind;
@@ -766,6 +1093,8 @@ impl AdvancementSum for PlantAdvancementSum {
         let mut crafting_speed_multiplier = 1.0;
         let mut craft_return_chance = 0.0;
         let mut recipes = vec![];
+        // seeds
+        let mut seed_yields = vec![];
 
         for k in unlocked.iter() {
             xp += k.xp;
@@ -779,6 +1108,8 @@ impl AdvancementSum for PlantAdvancementSum {
             match kind {
                 // misc
                 Neighbor(..) => {}
+                // plants only get these once their pedigree is checked, see Plant::advancements_sum
+                Pedigree(..) => {}
                 &TimeTicksMultiplier(multiplier) => time_ticks_multiplier *= multiplier,
                 &ExtraTimeTicks(extra) => extra_time_ticks += extra,
 
@@ -801,6 +1132,9 @@ impl AdvancementSum for PlantAdvancementSum {
                 &CraftReturnChance(chance) => craft_return_chance += chance,
                 &CraftSpeedMultiplier(multiplier) => crafting_speed_multiplier *= multiplier,
                 &DoubleCraftYield(chance) => double_craft_yield_chance += chance,
+
+                // seeds
+                &SeedYield(cp) => seed_yields.push(cp),
                 Craft(new_recipes) => recipes.append(
                     &mut new_recipes
                         .clone()
@@ -861,6 +1195,8 @@ impl AdvancementSum for PlantAdvancementSum {
             double_craft_yield_chance,
             craft_return_chance,
             recipes,
+            // seeds
+            seed_yields,
         }
     }
 
@@ -961,6 +1297,15 @@ impl<S: AdvancementSum> AdvancementSet<S> {
         )
     }
 
+    /// How much xp is needed to reach the advancement at the given index.
+    pub fn required_xp(&self, index: usize) -> Option<u64> {
+        if index > self.rest.len() {
+            None
+        } else {
+            Some(self.all().take(index + 1).map(|a| a.xp).sum())
+        }
+    }
+
     pub fn current(&self, xp: u64) -> &Advancement<S> {
         self.get(self.current_position(xp)).unwrap_or(&self.base)
     }
@@ -1025,6 +1370,14 @@ pub fn check_archetype_name_matches(config: &Config) -> Result<(), String> {
                         }
                     }
                 }
+                for (food, _) in ga.foods.iter() {
+                    if config.find_possession(food).is_err() {
+                        return Err(format!(
+                            "gotchi archetype {:?} claims it eats unknown possession archetype {:?}",
+                            a.name, food,
+                        ));
+                    }
+                }
                 for SelectivePlantAdvancement { keep_plants, .. } in &ga.plant_effects {
                     if let Err(e) = keep_plants.lookup_handles_in_config(&config) {
                         return Err(format!(
@@ -1082,7 +1435,7 @@ pub fn check_archetype_name_matches(config: &Config) -> Result<(), String> {
             }
             Craft(recipes) => {
                 for Recipe { makes, needs, .. } in recipes.iter() {
-                    for (resource, _) in makes.all().iter() {
+                    for resource in makes.possibilities() {
                         if config.find_possession(resource).is_err() {
                             return Err(format!(
                                 "Crafting advancement {:?} for plant {:?} produces unknown resource {:?}",
@@ -1113,6 +1466,25 @@ pub fn check_archetype_name_matches(config: &Config) -> Result<(), String> {
     Ok(())
 }
 
+#[test]
+fn market_checks() {
+    let general = CONFIG.find_market(&"General").unwrap().clone();
+    assert!(Market {
+        sales_tax_percent: 101,
+        ..general.clone()
+    }
+    .check()
+    .is_err());
+    assert!(Market {
+        min_price: 10,
+        max_price: Some(5),
+        ..general.clone()
+    }
+    .check()
+    .is_err());
+    assert!(general.check().is_ok());
+}
+
 #[test]
 fn archetype_name_matches() {
     check_archetype_name_matches(&*CONFIG).unwrap_or_else(|e| panic!("{}", e));
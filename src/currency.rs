@@ -0,0 +1,508 @@
+//! Keeps track of how much gp each Hacksteader has, and where it all came from.
+use crate::{AttributeParseError, Category, Item, Profile, TABLE_NAME};
+use humantime::{format_rfc3339, format_rfc3339_nanos, parse_rfc3339};
+use rusoto_dynamodb::{AttributeValue, DynamoDb, DynamoDbClient};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Why gp changed hands.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LedgerReason {
+    /// Contains: the id of the possession which was sold
+    Sale(uuid::Uuid),
+    /// Contains: the id of the possession which was bought
+    Purchase(uuid::Uuid),
+    /// Contains: the id of the gotchi which was harvested
+    Harvest(uuid::Uuid),
+    /// Contains: the steader id of the admin who granted the gp
+    AdminGrant(String),
+    /// Contains: the id of the possession which was put up for sale
+    ListingFee(uuid::Uuid),
+    /// Contains: the id of the trade offer which was accepted
+    Trade(uuid::Uuid),
+}
+impl LedgerReason {
+    fn tag(&self) -> &'static str {
+        use LedgerReason::*;
+        match self {
+            Sale(_) => "sale",
+            Purchase(_) => "purchase",
+            Harvest(_) => "harvest",
+            AdminGrant(_) => "admin_grant",
+            ListingFee(_) => "listing_fee",
+            Trade(_) => "trade",
+        }
+    }
+
+    fn id(&self) -> String {
+        use LedgerReason::*;
+        match self {
+            Sale(id) | Purchase(id) | Harvest(id) | ListingFee(id) | Trade(id) => id.to_string(),
+            AdminGrant(admin) => admin.clone(),
+        }
+    }
+
+    fn from_parts(tag: &str, id: &str) -> Result<Self, AttributeParseError> {
+        use AttributeParseError::*;
+        use LedgerReason::*;
+
+        let uuid = || uuid::Uuid::parse_str(id).map_err(|e| IdFieldParse("reason_id", e));
+        Ok(match tag {
+            "sale" => Sale(uuid()?),
+            "purchase" => Purchase(uuid()?),
+            "harvest" => Harvest(uuid()?),
+            "admin_grant" => AdminGrant(id.to_string()),
+            "listing_fee" => ListingFee(uuid()?),
+            "trade" => Trade(uuid()?),
+            _ => return Err(Custom("unknown ledger reason")),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Credit,
+    Debit,
+}
+
+/// A single change to a Hacksteader's balance. Entries are only ever appended to a ledger.
+/// In the database, each entry is stored as its own item, keyed by the steader it belongs to
+/// and when it was made, so that a Hacksteader's ledger can grow without bound.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    pub at: SystemTime,
+    pub direction: Direction,
+    pub amount: u64,
+    pub reason: LedgerReason,
+}
+impl LedgerEntry {
+    pub fn credit(amount: u64, reason: LedgerReason) -> Self {
+        Self {
+            at: SystemTime::now(),
+            direction: Direction::Credit,
+            amount,
+            reason,
+        }
+    }
+
+    pub fn debit(amount: u64, reason: LedgerReason) -> Self {
+        Self {
+            at: SystemTime::now(),
+            direction: Direction::Debit,
+            amount,
+            reason,
+        }
+    }
+
+    /// The key of the item this entry is stored under in the ledger of the given steader.
+    /// Sorting these keys sorts a ledger by time.
+    pub fn key_item(&self, steader: &str) -> Item {
+        [
+            ("cat".to_string(), Category::LedgerEntry.into_av()),
+            (
+                "id".to_string(),
+                AttributeValue {
+                    s: Some(format!("{}#{}", steader, format_rfc3339_nanos(self.at))),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
+    pub fn item(&self, steader: &str) -> Item {
+        let s = |s: String| AttributeValue {
+            s: Some(s),
+            ..Default::default()
+        };
+        let direction = match self.direction {
+            Direction::Credit => "credit",
+            Direction::Debit => "debit",
+        };
+
+        let mut item = self.key_item(steader);
+        item.insert("steader".to_string(), s(steader.to_string()));
+        item.insert("at".to_string(), s(format_rfc3339(self.at).to_string()));
+        item.insert("direction".to_string(), s(direction.to_string()));
+        item.insert(
+            "amount".to_string(),
+            AttributeValue {
+                n: Some(self.amount.to_string()),
+                ..Default::default()
+            },
+        );
+        item.insert("reason".to_string(), s(self.reason.tag().to_string()));
+        item.insert("reason_id".to_string(), s(self.reason.id()));
+        item
+    }
+
+    pub fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
+        use AttributeParseError::*;
+
+        let s = |field: &'static str| {
+            item.get(field)
+                .ok_or(MissingField(field))?
+                .s
+                .as_ref()
+                .ok_or(WronglyTypedField(field))
+        };
+        Ok(Self {
+            at: parse_rfc3339(s("at")?).map_err(|e| TimeFieldParse("at", e))?,
+            direction: match s("direction")?.as_str() {
+                "credit" => Direction::Credit,
+                "debit" => Direction::Debit,
+                _ => return Err(Custom("unknown ledger direction")),
+            },
+            amount: item
+                .get("amount")
+                .ok_or(MissingField("amount"))?
+                .n
+                .as_ref()
+                .ok_or(WronglyTypedField("amount"))?
+                .parse()
+                .map_err(|e| IntFieldParse("amount", e))?,
+            reason: LedgerReason::from_parts(s("reason")?, s("reason_id")?)?,
+        })
+    }
+
+    /// Every entry in the ledger of the given steader, oldest first.
+    pub async fn fetch_all_db(db: &DynamoDbClient, steader: &str) -> Result<Vec<Self>, String> {
+        let mut entries = vec![];
+        let mut exclusive_start_key = None;
+        loop {
+            let output = db
+                .query(rusoto_dynamodb::QueryInput {
+                    table_name: TABLE_NAME.to_string(),
+                    key_condition_expression: Some(
+                        "cat = :ledger_cat AND begins_with(id, :steader)".to_string(),
+                    ),
+                    expression_attribute_values: Some(
+                        [
+                            (":ledger_cat".to_string(), Category::LedgerEntry.into_av()),
+                            (
+                                ":steader".to_string(),
+                                AttributeValue {
+                                    s: Some(format!("{}#", steader)),
+                                    ..Default::default()
+                                },
+                            ),
+                        ]
+                        .iter()
+                        .cloned()
+                        .collect(),
+                    ),
+                    exclusive_start_key,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| format!("Couldn't fetch ledger: {}", e))?;
+
+            entries.extend(output.items.unwrap_or_default().iter().filter_map(|i| {
+                match LedgerEntry::from_item(i) {
+                    Ok(entry) => Some(entry),
+                    Err(e) => {
+                        println!("error parsing ledger entry: {}", e);
+                        None
+                    }
+                }
+            }));
+            exclusive_start_key = output.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum BalanceError {
+    /// Debiting this much would leave the balance below zero.
+    Insufficient { needs: u64, has: u64 },
+    /// The database refused to debit this much, because the balance there couldn't cover it.
+    Declined { needs: u64 },
+    /// Contains: why the balance couldn't be changed in the database
+    Db(String),
+}
+impl fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BalanceError::*;
+        match self {
+            Insufficient { needs, has } => write!(f, "needs {} gp, but only has {} gp", needs, has),
+            Declined { needs } => write!(f, "doesn't have the {} gp needed", needs),
+            Db(e) => write!(f, "couldn't update balance: {}", e),
+        }
+    }
+}
+
+/// How much gp a Hacksteader has, and every change to it made since it was loaded.
+/// The full ledger is kept in the database, see `LedgerEntry::fetch_all_db`.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct Wallet {
+    pub gp: u64,
+    pub ledger: Vec<LedgerEntry>,
+}
+impl Wallet {
+    /// Records the entry in the ledger and applies it to the balance,
+    /// unless it's a debit the balance can't cover. Returns the new balance.
+    pub fn transact(&mut self, entry: LedgerEntry) -> Result<u64, BalanceError> {
+        self.gp = match entry.direction {
+            Direction::Credit => self.gp + entry.amount,
+            Direction::Debit => {
+                self.gp
+                    .checked_sub(entry.amount)
+                    .ok_or(BalanceError::Insufficient {
+                        needs: entry.amount,
+                        has: self.gp,
+                    })?
+            }
+        };
+        self.ledger.push(entry);
+        Ok(self.gp)
+    }
+
+    pub fn credit(&mut self, amount: u64, reason: LedgerReason) -> u64 {
+        self.transact(LedgerEntry::credit(amount, reason))
+            .expect("credits can't fail")
+    }
+
+    pub fn debit(&mut self, amount: u64, reason: LedgerReason) -> Result<u64, BalanceError> {
+        self.transact(LedgerEntry::debit(amount, reason))
+    }
+
+    /// Profiles from before gp was tracked start out with an empty wallet.
+    pub fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
+        use AttributeParseError::*;
+
+        Ok(Self {
+            gp: match item.get("gp") {
+                Some(gp) => {
+                    gp.n.as_ref()
+                        .ok_or(WronglyTypedField("gp"))?
+                        .parse()
+                        .map_err(|e| IntFieldParse("gp", e))?
+                }
+                None => 0,
+            },
+            ledger: vec![],
+        })
+    }
+}
+
+impl Profile {
+    /// An update applying the entries to the balance of the profile with the given steader id,
+    /// all at once, since a transaction can only update each profile once.
+    /// Any debits are conditional on the balance covering all of them.
+    pub fn balance_update(id: String, entries: &[LedgerEntry]) -> rusoto_dynamodb::Update {
+        let n = |n: u64| AttributeValue {
+            n: Some(n.to_string()),
+            ..Default::default()
+        };
+        let total = |direction| {
+            entries
+                .iter()
+                .filter(|e| e.direction == direction)
+                .fold(0u64, |sum, e| sum.saturating_add(e.amount))
+        };
+        let (credit, debit) = (total(Direction::Credit), total(Direction::Debit));
+
+        let mut values = Item::new();
+        let (update, condition) = if debit == 0 {
+            values.insert(":amount".to_string(), n(credit));
+            values.insert(":zero".to_string(), n(0));
+            ("SET gp = if_not_exists(gp, :zero) + :amount", None)
+        } else {
+            values.insert(":debit".to_string(), n(debit));
+            let condition = Some("gp >= :debit".to_string());
+            if credit >= debit {
+                values.insert(":amount".to_string(), n(credit - debit));
+                ("SET gp = gp + :amount", condition)
+            } else {
+                values.insert(":amount".to_string(), n(debit - credit));
+                ("SET gp = gp - :amount", condition)
+            }
+        };
+
+        rusoto_dynamodb::Update {
+            table_name: TABLE_NAME.to_string(),
+            key: Profile::key_item(id),
+            update_expression: update.to_string(),
+            condition_expression: condition,
+            expression_attribute_values: Some(values),
+            ..Default::default()
+        }
+    }
+
+    /// Applies the entries to the balance of the profile with the given steader id and adds them
+    /// to their ledger, for use in a larger transaction.
+    pub fn balance_transaction(
+        id: String,
+        mut entries: Vec<LedgerEntry>,
+    ) -> Vec<rusoto_dynamodb::TransactWriteItem> {
+        // entries made at the same instant still need keys of their own
+        for i in 1..entries.len() {
+            let after = entries[i - 1].at + Duration::from_nanos(1);
+            entries[i].at = entries[i].at.max(after);
+        }
+
+        let mut transact_items = vec![rusoto_dynamodb::TransactWriteItem {
+            update: Some(Profile::balance_update(id.clone(), &entries)),
+            ..Default::default()
+        }];
+        transact_items.extend(
+            entries
+                .iter()
+                .map(|entry| rusoto_dynamodb::TransactWriteItem {
+                    put: Some(rusoto_dynamodb::Put {
+                        table_name: TABLE_NAME.to_string(),
+                        item: entry.item(&id),
+                        condition_expression: Some("attribute_not_exists(id)".to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+        );
+        transact_items
+    }
+
+    /// Applies the entry to the balance of the profile with the given steader id in the database.
+    /// Debits are conditional on the balance covering them, so it can never go negative
+    /// even if several updates race each other.
+    pub async fn transact_db(
+        db: &DynamoDbClient,
+        id: String,
+        entry: LedgerEntry,
+    ) -> Result<(), BalanceError> {
+        let amount = entry.amount;
+
+        db.transact_write_items(rusoto_dynamodb::TransactWriteItemsInput {
+            transact_items: Profile::balance_transaction(id, vec![entry]),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| match e {
+            rusoto_core::RusotoError::Service(
+                rusoto_dynamodb::TransactWriteItemsError::TransactionCanceled(_),
+            ) => BalanceError::Declined { needs: amount },
+            e => BalanceError::Db(e.to_string()),
+        })?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn wallet_never_goes_negative() -> Result<(), AttributeParseError> {
+    let gotchi = uuid::Uuid::new_v4();
+    let mut wallet = Wallet::default();
+
+    assert_eq!(wallet.credit(10, LedgerReason::Harvest(gotchi)), 10);
+    assert_eq!(
+        wallet.debit(11, LedgerReason::Purchase(gotchi)),
+        Err(BalanceError::Insufficient { needs: 11, has: 10 })
+    );
+    assert_eq!(wallet.gp, 10);
+    assert_eq!(wallet.ledger.len(), 1);
+    assert_eq!(wallet.debit(4, LedgerReason::Purchase(gotchi)), Ok(6));
+    wallet.credit(5, LedgerReason::AdminGrant("admin".to_string()));
+
+    // timestamps are only stored to the second
+    for entry in wallet.ledger.iter_mut() {
+        entry.at = parse_rfc3339(&format_rfc3339(entry.at).to_string()).unwrap();
+    }
+    for entry in &wallet.ledger {
+        assert_eq!(&LedgerEntry::from_item(&entry.item("bob"))?, entry);
+    }
+    assert_eq!(Wallet::from_item(&Item::new())?, Wallet::default());
+
+    Ok(())
+}
+
+#[test]
+fn balance_updates_use_every_value() {
+    let gotchi = uuid::Uuid::new_v4();
+    let credit = LedgerEntry::credit(5, LedgerReason::Harvest(gotchi));
+    let debit = LedgerEntry::debit(3, LedgerReason::Purchase(gotchi));
+    for entries in vec![
+        vec![credit.clone()],
+        vec![debit.clone()],
+        vec![credit.clone(), debit.clone()],
+        vec![debit.clone(), debit.clone()],
+    ] {
+        let update = Profile::balance_update("bob".to_string(), &entries);
+        let expressions = format!(
+            "{} {}",
+            update.update_expression,
+            update.condition_expression.unwrap_or_default()
+        );
+        for placeholder in update.expression_attribute_values.unwrap().keys() {
+            assert!(
+                expressions.contains(placeholder.as_str()),
+                "{} unused in {}",
+                placeholder,
+                expressions
+            );
+        }
+    }
+}
+
+#[test]
+fn balance_transactions_net_out_entries() {
+    let gotchi = uuid::Uuid::new_v4();
+    let credit = LedgerEntry::credit(5, LedgerReason::Harvest(gotchi));
+    let debit = LedgerEntry::debit(3, LedgerReason::Purchase(gotchi));
+    let amount = |entries: &[LedgerEntry]| {
+        let update = Profile::balance_update("bob".to_string(), entries);
+        let values = update.expression_attribute_values.unwrap();
+        (
+            update.update_expression,
+            values[":amount"].n.clone().unwrap(),
+            values.get(":debit").and_then(|v| v.n.clone()),
+        )
+    };
+
+    assert_eq!(
+        amount(&[credit.clone(), debit.clone()]),
+        (
+            "SET gp = gp + :amount".to_string(),
+            "2".to_string(),
+            Some("3".to_string())
+        )
+    );
+    assert_eq!(
+        amount(&[debit.clone(), debit.clone(), credit.clone()]),
+        (
+            "SET gp = gp - :amount".to_string(),
+            "1".to_string(),
+            Some("6".to_string())
+        )
+    );
+
+    // one update for the profile, and a ledger entry with a key of its own for each entry
+    let items = Profile::balance_transaction("bob".to_string(), vec![credit.clone(), credit]);
+    assert_eq!(items.len(), 3);
+    let keys: Vec<_> = items[1..]
+        .iter()
+        .map(|i| i.put.as_ref().unwrap().item["id"].s.clone())
+        .collect();
+    assert_ne!(keys[0], keys[1]);
+}
+
+#[test]
+fn saving_profiles_leaves_balances_alone() {
+    let mut profile = Profile::new("bob".to_string());
+    profile
+        .wallet
+        .credit(10, LedgerReason::AdminGrant("admin".to_string()));
+
+    assert!(!profile.item().contains_key("gp"));
+    let update = profile.update();
+    assert!(!update.update_expression.contains("gp"));
+    for placeholder in update.expression_attribute_values.unwrap().keys() {
+        assert!(update.update_expression.contains(placeholder.as_str()));
+    }
+}
@@ -12,10 +12,14 @@ pub mod errors;
 
 pub mod category;
 pub mod config;
+pub mod currency;
 
 pub mod market;
 pub mod models;
+pub mod odds;
 pub mod possess;
+pub mod simulate;
+pub mod trade;
 pub mod yank_config;
 
 pub mod frontend {
@@ -29,6 +33,8 @@ pub use config::CONFIG;
 pub use possess::{Possessed, Possession};
 
 pub const TABLE_NAME: &'static str = "hackagotchi";
+/// How often, in milliseconds, the plants on every hackstead are farmed.
+pub const FARM_CYCLE_MILLIS: u64 = 2000;
 pub type Item = HashMap<String, AttributeValue>;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -89,6 +95,7 @@ pub struct Profile {
     /// This is not an uuid::Uuid because it's actually the steader id of the person who owns this Profile
     pub id: String,
     pub xp: u64,
+    pub wallet: currency::Wallet,
 }
 
 impl std::ops::Deref for Profile {
@@ -107,6 +114,7 @@ impl Profile {
             last_farm: SystemTime::now(),
             xp: 0,
             id: owner_id,
+            wallet: Default::default(),
         }
     }
 
@@ -178,6 +186,7 @@ impl Profile {
         .collect()
     }
 
+    /// Leaves out the balance, so this is only for storing new profiles; see `create_db`.
     pub fn item(&self) -> Item {
         let mut m = Self::key_item(self.id.clone());
         m.insert(
@@ -218,6 +227,59 @@ impl Profile {
         m
     }
 
+    /// Stores this profile, as long as there isn't one for this Hacksteader already;
+    /// existing profiles are saved with `update_db`, which leaves their balance alone.
+    pub async fn create_db(&self, db: &DynamoDbClient) -> Result<(), String> {
+        db.put_item(rusoto_dynamodb::PutItemInput {
+            table_name: TABLE_NAME.to_string(),
+            item: self.item(),
+            condition_expression: Some("attribute_not_exists(id)".to_string()),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| format!("Couldn't create profile for {}: {}", self.id, e))?;
+
+        Ok(())
+    }
+
+    /// An update saving everything about this profile but its balance.
+    pub fn update(&self) -> rusoto_dynamodb::Update {
+        let mut item = self.item();
+        let values = ["steader", "joined", "last_active", "last_farm", "xp"]
+            .iter()
+            .map(|field| (format!(":{}", field), item.remove(*field).unwrap()))
+            .collect();
+
+        rusoto_dynamodb::Update {
+            table_name: TABLE_NAME.to_string(),
+            key: Self::key_item(self.id.clone()),
+            update_expression: concat!(
+                "SET steader = :steader, joined = :joined, last_active = :last_active, ",
+                "last_farm = :last_farm, xp = :xp",
+            )
+            .to_string(),
+            expression_attribute_values: Some(values),
+            ..Default::default()
+        }
+    }
+
+    /// Saves an existing profile without touching its balance, which only ever changes
+    /// through `Profile::balance_update`, so that an out of date Profile can't undo that.
+    pub async fn update_db(&self, db: &DynamoDbClient) -> Result<(), String> {
+        let update = self.update();
+        db.update_item(rusoto_dynamodb::UpdateItemInput {
+            table_name: update.table_name,
+            key: update.key,
+            update_expression: Some(update.update_expression),
+            expression_attribute_values: update.expression_attribute_values,
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| format!("Couldn't save profile for {}: {}", self.id, e))?;
+
+        Ok(())
+    }
+
     pub fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
         use AttributeParseError::*;
         Ok(Self {
@@ -261,13 +323,14 @@ impl Profile {
                     .ok_or(WronglyTypedField("last_farm"))?,
             )
             .map_err(|e| TimeFieldParse("last_farm", e))?,
+            wallet: currency::Wallet::from_item(item)?,
         })
     }
 }
 
 /// A model for all keys that use uuid:Uuids internally,
 /// essentially all those except Profile keys.
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq)]
 pub struct Key {
     pub category: Category,
     pub id: uuid::Uuid,
@@ -1,4 +1,13 @@
-use crate::AttributeParseError;
+use crate::config::{ArchetypeHandle, ArchetypeKind, Market};
+use crate::currency::{LedgerEntry, LedgerReason, Wallet};
+use crate::possess::gotchi::{HARVEST_CONDITION, HARVEST_SET};
+use crate::possess::{Acquisition, Owner};
+use crate::{AttributeParseError, Category, Item, Key, Possession, Profile, CONFIG, TABLE_NAME};
+use humantime::format_rfc3339_nanos;
+use rusoto_dynamodb::{AttributeValue, DynamoDb, DynamoDbClient};
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, SystemTime};
 
 #[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Sale {
@@ -20,4 +29,1438 @@ impl Sale {
             price: i.get("price")?.n.as_ref()?.parse().ok()?,
         })
     }
+
+    pub fn write_item(&self, item: &mut Item) {
+        item.insert(
+            "market_name".to_string(),
+            AttributeValue {
+                s: Some(self.market_name.clone()),
+                ..Default::default()
+            },
+        );
+        item.insert(
+            "price".to_string(),
+            AttributeValue {
+                n: Some(self.price.to_string()),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+fn is_special(steader: &str) -> bool {
+    CONFIG.special_users.iter().any(|u| u == steader)
+}
+
+/// How much this market takes from someone selling something there for this much.
+/// Special users sell without paying any fees.
+pub fn sales_tax(market: &Market, price: u64, seller: &str) -> u64 {
+    if is_special(seller) {
+        0
+    } else {
+        match price.checked_mul(market.sales_tax_percent) {
+            Some(taxed) => taxed / 100,
+            // markets can't tax more than 100%, so this can't overflow
+            None => price / 100 * market.sales_tax_percent,
+        }
+    }
+}
+
+/// How much it costs this seller to put something up for sale on this market.
+pub fn listing_fee(market: &Market, seller: &str) -> u64 {
+    if is_special(seller) {
+        0
+    } else {
+        market.listing_fee
+    }
+}
+
+/// Makes sure a listing for this possession at this price is allowed on this market.
+/// Nothing can be given away for free, whatever the market's minimum price.
+pub fn check_listing(
+    market: &Market,
+    possession: &Possession,
+    price: u64,
+) -> Result<(), MarketError> {
+    use MarketError::*;
+
+    let archetype = CONFIG
+        .possession_archetypes
+        .get(possession.archetype_handle);
+    if !market.kinds.is_empty()
+        && !market
+            .kinds
+            .iter()
+            .any(|k| archetype.map_or(false, |a| k.matches(&a.kind)))
+    {
+        return Err(KindNotAllowed {
+            market: market.name.clone(),
+            archetype: possession.name.clone(),
+        });
+    }
+    let min = market.min_price.max(1);
+    if price < min {
+        return Err(PriceTooLow { min });
+    }
+    match market.max_price {
+        Some(max) if price > max => Err(PriceTooHigh { max }),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum MarketError {
+    /// Contains: the steader id of whoever tried to sell something they don't own
+    NotOwner(String),
+    /// Contains: the name of the market which isn't in the config
+    UnknownMarket(String),
+    KindNotAllowed {
+        market: String,
+        archetype: String,
+    },
+    PriceTooLow {
+        min: u64,
+    },
+    PriceTooHigh {
+        max: u64,
+    },
+    AlreadyListed,
+    NotListed,
+    /// Nobody can buy what they're selling themselves.
+    OwnListing,
+    Config(crate::config::ConfigError),
+    Balance(crate::currency::BalanceError),
+    /// The listing, or someone's balance, changed before the transaction could go through.
+    Conflict,
+    /// Contains: why the database couldn't be updated
+    Db(String),
+}
+impl fmt::Display for MarketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MarketError::*;
+        match self {
+            NotOwner(id) => write!(f, "{} can't sell something they don't own", id),
+            UnknownMarket(name) => write!(f, "there's no market called {:?}", name),
+            KindNotAllowed { market, archetype } => {
+                write!(f, "{} can't be sold on the {} market", archetype, market)
+            }
+            PriceTooLow { min } => write!(f, "this market doesn't allow prices below {}gp", min),
+            PriceTooHigh { max } => write!(f, "this market doesn't allow prices above {}gp", max),
+            AlreadyListed => write!(f, "this is already up for sale"),
+            NotListed => write!(f, "this isn't up for sale"),
+            OwnListing => write!(f, "you can't buy something you're selling yourself"),
+            Config(e) => write!(f, "{}", e),
+            Balance(e) => write!(f, "{}", e),
+            Conflict => write!(f, "this listing changed before it could be bought"),
+            Db(e) => write!(f, "couldn't update the market: {}", e),
+        }
+    }
+}
+impl From<crate::config::ConfigError> for MarketError {
+    fn from(e: crate::config::ConfigError) -> Self {
+        match e {
+            crate::config::ConfigError::UnknownMarket(name) => MarketError::UnknownMarket(name),
+            e => MarketError::Config(e),
+        }
+    }
+}
+impl From<crate::currency::BalanceError> for MarketError {
+    fn from(e: crate::currency::BalanceError) -> Self {
+        MarketError::Balance(e)
+    }
+}
+
+/// Puts a possession up for sale on the given market, as long as that market's rules allow it
+/// and the seller can afford its listing fee, which is taken out of their wallet.
+/// Returns the ledger entry for the fee, if there is one, for `list_db` to store.
+pub fn list(
+    possession: &mut Possession,
+    seller: &str,
+    seller_wallet: &mut Wallet,
+    price: u64,
+    market_name: String,
+) -> Result<Option<LedgerEntry>, MarketError> {
+    if possession.steader != seller {
+        return Err(MarketError::NotOwner(seller.to_string()));
+    }
+    if possession.sale.is_some() {
+        return Err(MarketError::AlreadyListed);
+    }
+    let market = CONFIG.find_market(&market_name)?;
+    check_listing(market, possession, price)?;
+
+    let fee = match listing_fee(market, seller) {
+        0 => None,
+        fee => {
+            let entry = LedgerEntry::debit(fee, LedgerReason::ListingFee(possession.id));
+            seller_wallet.transact(entry.clone())?;
+            Some(entry)
+        }
+    };
+
+    possession.sale = Some(Sale { price, market_name });
+    Ok(fee)
+}
+
+/// Takes a possession off of the market, returning the listing it had.
+pub fn delist(possession: &mut Possession, seller: &str) -> Result<Sale, MarketError> {
+    if possession.steader != seller {
+        return Err(MarketError::NotOwner(seller.to_string()));
+    }
+    possession.sale.take().ok_or(MarketError::NotListed)
+}
+
+/// Everything that changes hands when a listed possession is bought.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Purchase {
+    pub possession: uuid::Uuid,
+    pub archetype_handle: ArchetypeHandle,
+    pub at: SystemTime,
+    pub seller: String,
+    pub buyer: String,
+    pub sale: Sale,
+    /// How much of the price the market keeps as sales tax; the seller gets the rest.
+    pub fee: u64,
+}
+impl Purchase {
+    /// Describes the buyer buying this possession at its listed price.
+    pub fn of(possession: &Possession, buyer: &str) -> Result<Self, MarketError> {
+        let sale = possession.sale.clone().ok_or(MarketError::NotListed)?;
+        if possession.steader == buyer {
+            return Err(MarketError::OwnListing);
+        }
+
+        Ok(Self {
+            possession: possession.id,
+            archetype_handle: possession.archetype_handle,
+            at: SystemTime::now(),
+            seller: possession.steader.clone(),
+            buyer: buyer.to_string(),
+            fee: sales_tax(
+                CONFIG.find_market(&sale.market_name)?,
+                sale.price,
+                &possession.steader,
+            ),
+            sale,
+        })
+    }
+
+    pub fn proceeds(&self) -> u64 {
+        self.sale.price.saturating_sub(self.fee)
+    }
+
+    fn debit(&self) -> LedgerEntry {
+        LedgerEntry::debit(self.sale.price, LedgerReason::Purchase(self.possession))
+    }
+
+    fn credit(&self) -> LedgerEntry {
+        LedgerEntry::credit(self.proceeds(), LedgerReason::Sale(self.possession))
+    }
+
+    pub fn event(&self) -> SaleEvent {
+        SaleEvent {
+            id: uuid::Uuid::new_v4(),
+            archetype_handle: self.archetype_handle,
+            price: self.sale.price,
+            at: self.at,
+            market_name: self.sale.market_name.clone(),
+        }
+    }
+
+    fn owner(&self) -> Owner {
+        Owner {
+            id: self.buyer.clone(),
+            acquisition: Acquisition::Purchase {
+                price: self.sale.price,
+            },
+        }
+    }
+}
+
+/// Buys a listed possession for the buyer, paying its seller and handing it over.
+/// Nothing changes unless the buyer can afford it.
+pub fn buy(
+    possession: &mut Possession,
+    buyer: &str,
+    buyer_wallet: &mut Wallet,
+    seller_wallet: &mut Wallet,
+) -> Result<Purchase, MarketError> {
+    let purchase = Purchase::of(possession, buyer)?;
+
+    buyer_wallet.transact(purchase.debit())?;
+    // whatever a gotchi earned while the seller had it is theirs
+    match possession.harvest(purchase.at) {
+        Some(harvest) if harvest.amount > 0 => {
+            seller_wallet.transact(harvest.entry())?;
+        }
+        _ => {}
+    }
+    seller_wallet.transact(purchase.credit())?;
+
+    possession.steader = purchase.buyer.clone();
+    possession.ownership_log.push(purchase.owner());
+    possession.sale = None;
+
+    Ok(purchase)
+}
+
+fn sale_key(id: uuid::Uuid) -> Item {
+    Key {
+        category: Category::Sale,
+        id,
+    }
+    .into_item()
+}
+
+pub(crate) fn values(values: Vec<(&str, AttributeValue)>) -> Option<Item> {
+    Some(
+        values
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+pub(crate) fn s(s: &str) -> AttributeValue {
+    AttributeValue {
+        s: Some(s.to_string()),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn n(n: u64) -> AttributeValue {
+    AttributeValue {
+        n: Some(n.to_string()),
+        ..Default::default()
+    }
+}
+
+/// The entry for a listed possession in the Sale category, which the market is searched through.
+pub fn sale_item(possession: &Possession) -> Option<Item> {
+    let sale = possession.sale.as_ref()?;
+    let mut item = sale_key(possession.id);
+    sale.write_item(&mut item);
+    item.insert("steader".to_string(), s(&possession.steader));
+    item.insert(
+        "archetype_handle".to_string(),
+        n(possession.archetype_handle as u64),
+    );
+    item.insert("category".to_string(), possession.kind.category().into_av());
+    Some(item)
+}
+
+async fn transact(
+    db: &DynamoDbClient,
+    transact_items: Vec<rusoto_dynamodb::TransactWriteItem>,
+) -> Result<(), MarketError> {
+    db.transact_write_items(rusoto_dynamodb::TransactWriteItemsInput {
+        transact_items,
+        ..Default::default()
+    })
+    .await
+    .map_err(|e| match e {
+        rusoto_core::RusotoError::Service(
+            rusoto_dynamodb::TransactWriteItemsError::TransactionCanceled(_),
+        ) => MarketError::Conflict,
+        e => MarketError::Db(e.to_string()),
+    })?;
+
+    Ok(())
+}
+
+/// Stores a listing made with `list`, along with the listing fee it charged,
+/// as long as the possession wasn't listed or sold already and the seller can pay that fee.
+pub async fn list_db(
+    db: &DynamoDbClient,
+    possession: &Possession,
+    fee: Option<&LedgerEntry>,
+) -> Result<(), MarketError> {
+    let sale = possession.sale.as_ref().ok_or(MarketError::NotListed)?;
+    let market = CONFIG.find_market(&sale.market_name)?;
+    check_listing(market, possession, sale.price)?;
+
+    let pay_fee = match fee {
+        Some(entry) => {
+            Profile::balance_transaction(possession.steader.clone(), vec![entry.clone()])
+        }
+        None => vec![],
+    };
+
+    transact(
+        db,
+        pay_fee
+            .into_iter()
+            .chain(vec![
+                rusoto_dynamodb::TransactWriteItem {
+                    update: Some(rusoto_dynamodb::Update {
+                        table_name: TABLE_NAME.to_string(),
+                        key: possession.key().into_item(),
+                        update_expression: "SET price = :price, market_name = :market_name"
+                            .to_string(),
+                        condition_expression: Some(
+                            "steader = :steader AND attribute_not_exists(price)".to_string(),
+                        ),
+                        expression_attribute_values: values(vec![
+                            (":price", n(sale.price)),
+                            (":market_name", s(&sale.market_name)),
+                            (":steader", s(&possession.steader)),
+                        ]),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                rusoto_dynamodb::TransactWriteItem {
+                    put: Some(rusoto_dynamodb::Put {
+                        table_name: TABLE_NAME.to_string(),
+                        item: sale_item(possession).ok_or(MarketError::NotListed)?,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ])
+            .collect(),
+    )
+    .await
+}
+
+/// Takes a possession's listing off of the market in the database.
+pub async fn delist_db(db: &DynamoDbClient, possession: &Possession) -> Result<(), MarketError> {
+    transact(
+        db,
+        vec![
+            rusoto_dynamodb::TransactWriteItem {
+                update: Some(rusoto_dynamodb::Update {
+                    table_name: TABLE_NAME.to_string(),
+                    key: possession.key().into_item(),
+                    update_expression: "REMOVE price, market_name".to_string(),
+                    condition_expression: Some(
+                        "steader = :steader AND attribute_exists(price)".to_string(),
+                    ),
+                    expression_attribute_values: values(vec![(":steader", s(&possession.steader))]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            rusoto_dynamodb::TransactWriteItem {
+                delete: Some(rusoto_dynamodb::Delete {
+                    table_name: TABLE_NAME.to_string(),
+                    key: sale_key(possession.id),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ],
+    )
+    .await
+}
+
+/// Carries out a purchase in the database all at once: the buyer pays, the seller is paid,
+/// the possession changes hands and the sale is recorded for price history, or, if the buyer
+/// can't afford it or the listing changed in the meantime, nothing happens at all.
+/// Gotchis are harvested before they change hands, and the seller is credited with that too.
+pub async fn buy_db(
+    db: &DynamoDbClient,
+    possession: &Possession,
+    purchase: &Purchase,
+) -> Result<(), MarketError> {
+    let mut update_values = vec![
+        (":buyer", s(&purchase.buyer)),
+        (":seller", s(&purchase.seller)),
+        (":price", n(purchase.sale.price)),
+        (":market_name", s(&purchase.sale.market_name)),
+        (
+            ":owner",
+            AttributeValue {
+                l: Some(vec![purchase.owner().into()]),
+                ..Default::default()
+            },
+        ),
+    ];
+    let mut seller_entries = vec![];
+    let (harvest_set, harvest_condition) = match possession.harvest_update(purchase.at) {
+        Some((harvest, harvest_values)) => {
+            update_values.extend(harvest_values);
+            if harvest.amount > 0 {
+                seller_entries.push(harvest.entry());
+            }
+            (
+                format!(", {}", HARVEST_SET),
+                format!(" AND {}", HARVEST_CONDITION),
+            )
+        }
+        None => (String::new(), String::new()),
+    };
+    seller_entries.push(purchase.credit());
+
+    transact(
+        db,
+        vec![
+            rusoto_dynamodb::TransactWriteItem {
+                update: Some(rusoto_dynamodb::Update {
+                    table_name: TABLE_NAME.to_string(),
+                    key: possession.key().into_item(),
+                    update_expression: format!(
+                        concat!(
+                            "SET steader = :buyer, ",
+                            "ownership_log = list_append(ownership_log, :owner){} ",
+                            "REMOVE price, market_name",
+                        ),
+                        harvest_set
+                    ),
+                    condition_expression: Some(format!(
+                        "steader = :seller AND price = :price AND market_name = :market_name{}",
+                        harvest_condition
+                    )),
+                    expression_attribute_values: values(update_values),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            rusoto_dynamodb::TransactWriteItem {
+                delete: Some(rusoto_dynamodb::Delete {
+                    table_name: TABLE_NAME.to_string(),
+                    key: sale_key(purchase.possession),
+                    condition_expression: Some("attribute_exists(price)".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            rusoto_dynamodb::TransactWriteItem {
+                put: Some(rusoto_dynamodb::Put {
+                    table_name: TABLE_NAME.to_string(),
+                    item: purchase.event().item(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ]
+        .into_iter()
+        .chain(Profile::balance_transaction(
+            purchase.buyer.clone(),
+            vec![purchase.debit()],
+        ))
+        .chain(Profile::balance_transaction(
+            purchase.seller.clone(),
+            seller_entries,
+        ))
+        .collect(),
+    )
+    .await
+}
+
+/// A record of something being bought on the market.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SaleEvent {
+    pub id: uuid::Uuid,
+    pub archetype_handle: ArchetypeHandle,
+    pub price: u64,
+    pub at: SystemTime,
+    pub market_name: String,
+}
+impl SaleEvent {
+    /// The key of the item this event is stored under. Events of the same archetype
+    /// share a prefix, and sorting their keys sorts them by time.
+    pub fn key_item(&self) -> Item {
+        [
+            ("cat".to_string(), Category::SaleEvent.into_av()),
+            (
+                "id".to_string(),
+                s(&format!(
+                    "{}#{}#{}",
+                    self.archetype_handle,
+                    format_rfc3339_nanos(self.at),
+                    self.id
+                )),
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
+    pub fn item(&self) -> Item {
+        let mut item = self.key_item();
+        item.insert("event_id".to_string(), s(&self.id.to_string()));
+        item.insert(
+            "archetype_handle".to_string(),
+            n(self.archetype_handle as u64),
+        );
+        item.insert("price".to_string(), n(self.price));
+        item.insert(
+            "at".to_string(),
+            s(&humantime::format_rfc3339(self.at).to_string()),
+        );
+        item.insert("market_name".to_string(), s(&self.market_name));
+        item
+    }
+
+    pub fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
+        use AttributeParseError::*;
+
+        let field = |name: &'static str| item.get(name).ok_or(MissingField(name));
+        Ok(Self {
+            id: uuid::Uuid::parse_str(
+                field("event_id")?
+                    .s
+                    .as_ref()
+                    .ok_or(WronglyTypedField("event_id"))?,
+            )
+            .map_err(|e| IdFieldParse("event_id", e))?,
+            archetype_handle: field("archetype_handle")?
+                .n
+                .as_ref()
+                .ok_or(WronglyTypedField("archetype_handle"))?
+                .parse()
+                .map_err(|e| IntFieldParse("archetype_handle", e))?,
+            price: field("price")?
+                .n
+                .as_ref()
+                .ok_or(WronglyTypedField("price"))?
+                .parse()
+                .map_err(|e| IntFieldParse("price", e))?,
+            at: humantime::parse_rfc3339(field("at")?.s.as_ref().ok_or(WronglyTypedField("at"))?)
+                .map_err(|e| TimeFieldParse("at", e))?,
+            market_name: field("market_name")?
+                .s
+                .as_ref()
+                .ok_or(WronglyTypedField("market_name"))?
+                .clone(),
+        })
+    }
+
+    /// Every recorded sale of the given archetype, oldest first.
+    pub async fn fetch_all_db(
+        db: &DynamoDbClient,
+        archetype_handle: ArchetypeHandle,
+    ) -> Result<Vec<SaleEvent>, String> {
+        let mut events = vec![];
+        let mut exclusive_start_key = None;
+        loop {
+            let output = db
+                .query(rusoto_dynamodb::QueryInput {
+                    table_name: TABLE_NAME.to_string(),
+                    key_condition_expression: Some(
+                        "cat = :event_cat AND begins_with(id, :ah)".to_string(),
+                    ),
+                    expression_attribute_values: values(vec![
+                        (":event_cat", Category::SaleEvent.into_av()),
+                        (":ah", s(&format!("{}#", archetype_handle))),
+                    ]),
+                    exclusive_start_key,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| format!("Couldn't fetch sale events: {}", e))?;
+
+            events.extend(output.items.unwrap_or_default().iter().filter_map(|i| {
+                match SaleEvent::from_item(i) {
+                    Ok(event) => Some(event),
+                    Err(e) => {
+                        println!("error parsing sale event: {}", e);
+                        None
+                    }
+                }
+            }));
+            exclusive_start_key = output.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// Summarizes what something has sold for.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PriceStats {
+    pub count: usize,
+    pub min: u64,
+    pub median: u64,
+    pub max: u64,
+}
+impl PriceStats {
+    /// Returns None if there are no prices to summarize.
+    pub fn of(prices: impl IntoIterator<Item = u64>) -> Option<Self> {
+        let mut prices: Vec<u64> = prices.into_iter().collect();
+        prices.sort();
+        let count = prices.len();
+        let mid = count / 2;
+
+        Some(Self {
+            count,
+            min: *prices.first()?,
+            median: if count % 2 == 0 {
+                let (a, b) = (prices[mid - 1], prices[mid]);
+                a + (b - a) / 2
+            } else {
+                prices[mid]
+            },
+            max: *prices.last()?,
+        })
+    }
+
+    /// Summarizes what this archetype was bought for, according to the ownership logs of these
+    /// possessions. This includes purchases made before sale events were recorded,
+    /// but can't say when they happened.
+    pub fn logged<'a>(
+        possessions: impl IntoIterator<Item = &'a Possession>,
+        archetype_handle: ArchetypeHandle,
+    ) -> Option<Self> {
+        Self::of(
+            possessions
+                .into_iter()
+                .filter(|p| p.archetype_handle == archetype_handle)
+                .flat_map(|p| p.ownership_log.iter())
+                .filter_map(|o| match o.acquisition {
+                    Acquisition::Purchase { price } => Some(price),
+                    _ => None,
+                }),
+        )
+    }
+}
+
+/// What something sold for during one stretch of time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PriceBucket {
+    pub start: SystemTime,
+    pub stats: PriceStats,
+}
+
+/// Groups the sales of an archetype into buckets spanning this long, counting from the
+/// unix epoch, in order of when they start. Buckets no sales fell into are left out.
+pub fn price_history<'a>(
+    events: impl IntoIterator<Item = &'a SaleEvent>,
+    archetype_handle: ArchetypeHandle,
+    bucket: Duration,
+) -> Vec<PriceBucket> {
+    let bucket_secs = bucket.as_secs().max(1);
+    let mut buckets: std::collections::BTreeMap<u64, Vec<u64>> = Default::default();
+    for event in events
+        .into_iter()
+        .filter(|e| e.archetype_handle == archetype_handle)
+    {
+        let secs = event
+            .at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        buckets
+            .entry(secs / bucket_secs)
+            .or_default()
+            .push(event.price);
+    }
+
+    buckets
+        .into_iter()
+        .filter_map(|(i, prices)| {
+            Some(PriceBucket {
+                start: SystemTime::UNIX_EPOCH + Duration::from_secs(i * bucket_secs),
+                stats: PriceStats::of(prices)?,
+            })
+        })
+        .collect()
+}
+
+/// A possession up for sale, as seen by someone browsing the market.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Listing {
+    pub id: uuid::Uuid,
+    pub archetype_handle: ArchetypeHandle,
+    pub seller: String,
+    pub sale: Sale,
+}
+impl Listing {
+    pub fn of(possession: &Possession) -> Option<Self> {
+        Some(Self {
+            id: possession.id,
+            archetype_handle: possession.archetype_handle,
+            seller: possession.steader.clone(),
+            sale: possession.sale.clone()?,
+        })
+    }
+
+    /// Parses the entries `sale_item` makes in the Sale category.
+    pub fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
+        use AttributeParseError::*;
+
+        Ok(Self {
+            id: Key::from_item(item)?.id,
+            archetype_handle: item
+                .get("archetype_handle")
+                .ok_or(MissingField("archetype_handle"))?
+                .n
+                .as_ref()
+                .ok_or(WronglyTypedField("archetype_handle"))?
+                .parse()
+                .map_err(|e| IntFieldParse("archetype_handle", e))?,
+            seller: item
+                .get("steader")
+                .ok_or(MissingField("steader"))?
+                .s
+                .as_ref()
+                .ok_or(WronglyTypedField("steader"))?
+                .clone(),
+            sale: Sale::from_item(item)?,
+        })
+    }
+
+    /// None if this listing's archetype has since been removed from the config.
+    pub fn archetype(&self) -> Option<&'static crate::config::Archetype> {
+        CONFIG.possession_archetypes.get(self.archetype_handle)
+    }
+}
+
+/// Which kind of possession to search the market for.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+pub enum KindFilter {
+    Gotchi,
+    Seed,
+    Keepsake,
+}
+impl KindFilter {
+    pub fn matches(self, kind: &ArchetypeKind) -> bool {
+        match (self, kind) {
+            (KindFilter::Gotchi, ArchetypeKind::Gotchi(_)) => true,
+            (KindFilter::Seed, ArchetypeKind::Seed(_)) => true,
+            (KindFilter::Keepsake, ArchetypeKind::Keepsake(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, serde::Deserialize)]
+pub enum SortBy {
+    Cheapest,
+    Priciest,
+    /// Alphabetically by the name of the archetype.
+    Name,
+}
+impl Default for SortBy {
+    fn default() -> Self {
+        SortBy::Cheapest
+    }
+}
+
+/// No page of market listings holds more than this many.
+pub const MAX_PER_PAGE: usize = 100;
+
+/// DynamoDB won't compare against more than this many values with IN.
+const MAX_IN_OPERANDS: usize = 100;
+
+/// Narrows down which listings to show someone browsing the market.
+/// Filters left as None let every listing through.
+#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+pub struct MarketQuery {
+    pub kind: Option<KindFilter>,
+    pub archetype_name: Option<String>,
+    pub min_price: Option<u64>,
+    pub max_price: Option<u64>,
+    pub seller: Option<String>,
+    pub market_name: Option<String>,
+    pub sort: SortBy,
+    /// Starts at 0.
+    pub page: usize,
+    /// At least 1 and at most `MAX_PER_PAGE`; anything else is clamped to fit.
+    pub per_page: usize,
+}
+impl Default for MarketQuery {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            archetype_name: None,
+            min_price: None,
+            max_price: None,
+            seller: None,
+            market_name: None,
+            sort: SortBy::default(),
+            page: 0,
+            per_page: 10,
+        }
+    }
+}
+
+/// One page of the listings a MarketQuery found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketPage {
+    pub listings: Vec<Listing>,
+    /// How many listings matched across every page.
+    pub total: usize,
+    pub page: usize,
+    pub pages: usize,
+}
+
+/// One page of the listings a MarketQuery found in the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketDbPage {
+    /// Sorted the way the query asks, but only among themselves.
+    pub listings: Vec<Listing>,
+    /// Where the next page picks up, unless this was the last one.
+    pub next: Option<Item>,
+}
+
+impl MarketQuery {
+    /// Listings with archetypes that aren't in the config never match.
+    pub fn matches(&self, listing: &Listing) -> bool {
+        let archetype = match listing.archetype() {
+            Some(archetype) => archetype,
+            None => return false,
+        };
+
+        self.kind.map_or(true, |k| k.matches(&archetype.kind))
+            && self
+                .archetype_name
+                .as_ref()
+                .map_or(true, |name| *name == archetype.name)
+            && self.min_price.map_or(true, |min| listing.sale.price >= min)
+            && self.max_price.map_or(true, |max| listing.sale.price <= max)
+            && self.seller.as_ref().map_or(true, |s| *s == listing.seller)
+            && self
+                .market_name
+                .as_ref()
+                .map_or(true, |m| *m == listing.sale.market_name)
+    }
+
+    fn per_page(&self) -> usize {
+        self.per_page.max(1).min(MAX_PER_PAGE)
+    }
+
+    fn sort(&self, listings: &mut Vec<Listing>) {
+        match self.sort {
+            SortBy::Cheapest => listings.sort_by_key(|l| l.sale.price),
+            SortBy::Priciest => listings.sort_by_key(|l| std::cmp::Reverse(l.sale.price)),
+            SortBy::Name => listings.sort_by(|a, b| {
+                let name = |l: &Listing| l.archetype().map(|a| a.name.clone());
+                name(a).cmp(&name(b)).then(a.sale.price.cmp(&b.sale.price))
+            }),
+        }
+    }
+
+    /// Filters, sorts and pages through the given listings.
+    pub fn search(&self, listings: impl IntoIterator<Item = Listing>) -> MarketPage {
+        let mut found: Vec<Listing> = listings.into_iter().filter(|l| self.matches(l)).collect();
+        self.sort(&mut found);
+
+        let per_page = self.per_page();
+        let total = found.len();
+        MarketPage {
+            listings: found
+                .into_iter()
+                .skip(self.page.saturating_mul(per_page))
+                .take(per_page)
+                .collect(),
+            total,
+            page: self.page,
+            pages: (total + per_page - 1) / per_page,
+        }
+    }
+
+    /// Searches the listings held in memory, i.e. the possessions which are up for sale.
+    pub fn search_possessions<'a>(
+        &self,
+        possessions: impl IntoIterator<Item = &'a Possession>,
+    ) -> MarketPage {
+        self.search(possessions.into_iter().filter_map(Listing::of))
+    }
+
+    /// The filter expression for the database to apply in `search_db`, and the values it uses,
+    /// or None if nothing in the config could match.
+    fn db_filter(&self) -> Option<(Option<String>, Item)> {
+        let mut filters = vec![];
+        let mut attribute_values = values(vec![(":sale_cat", Category::Sale.into_av())])?;
+        if let Some(min) = self.min_price {
+            filters.push("price >= :min_price".to_string());
+            attribute_values.insert(":min_price".to_string(), n(min));
+        }
+        if let Some(max) = self.max_price {
+            filters.push("price <= :max_price".to_string());
+            attribute_values.insert(":max_price".to_string(), n(max));
+        }
+        if let Some(seller) = &self.seller {
+            filters.push("steader = :seller".to_string());
+            attribute_values.insert(":seller".to_string(), s(seller));
+        }
+        if let Some(market_name) = &self.market_name {
+            filters.push("market_name = :market_name".to_string());
+            attribute_values.insert(":market_name".to_string(), s(market_name));
+        }
+        if let Some(name) = &self.archetype_name {
+            let handle = CONFIG.find_possession_handle(name).ok()?;
+            filters.push("archetype_handle = :archetype_handle".to_string());
+            attribute_values.insert(":archetype_handle".to_string(), n(handle as u64));
+        }
+        if let Some(kind) = self.kind {
+            let handles: Vec<usize> = CONFIG
+                .possession_archetypes
+                .iter()
+                .enumerate()
+                .filter(|(_, a)| kind.matches(&a.kind))
+                .map(|(handle, _)| handle)
+                .collect();
+            if handles.is_empty() {
+                return None;
+            }
+            // otherwise, `matches` still weeds out the other kinds once they're fetched
+            if handles.len() <= MAX_IN_OPERANDS {
+                let mut placeholders = vec![];
+                for handle in handles {
+                    let placeholder = format!(":kind_{}", handle);
+                    attribute_values.insert(placeholder.clone(), n(handle as u64));
+                    placeholders.push(placeholder);
+                }
+                filters.push(format!("archetype_handle IN ({})", placeholders.join(", ")));
+            }
+        }
+
+        let filter_expression = if filters.is_empty() {
+            None
+        } else {
+            Some(filters.join(" AND "))
+        };
+        Some((filter_expression, attribute_values))
+    }
+
+    /// Searches the Sale category of the database a page at a time, with every filter
+    /// applied by the database. Rather than by `page`, pages are picked out by passing
+    /// the `next` of the one before as `start`, and each page is only sorted on its own.
+    pub async fn search_db(
+        &self,
+        db: &DynamoDbClient,
+        start: Option<Item>,
+    ) -> Result<MarketDbPage, String> {
+        let (filter_expression, attribute_values) = match self.db_filter() {
+            Some(filter) => filter,
+            None => {
+                return Ok(MarketDbPage {
+                    listings: vec![],
+                    next: None,
+                })
+            }
+        };
+
+        let per_page = self.per_page();
+        let mut listings = vec![];
+        let mut exclusive_start_key = start;
+        loop {
+            let output = db
+                .query(rusoto_dynamodb::QueryInput {
+                    table_name: TABLE_NAME.to_string(),
+                    key_condition_expression: Some("cat = :sale_cat".to_string()),
+                    filter_expression: filter_expression.clone(),
+                    expression_attribute_values: Some(attribute_values.clone()),
+                    // the limit applies before the filter, so a page never overflows
+                    limit: Some((per_page - listings.len()) as i64),
+                    exclusive_start_key,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| format!("Couldn't search market: {}", e))?;
+
+            listings.extend(
+                output
+                    .items
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|i| match Listing::from_item(i) {
+                        Ok(listing) => Some(listing),
+                        Err(e) => {
+                            println!("error parsing listing: {}", e);
+                            None
+                        }
+                    })
+                    .filter(|l| self.matches(l)),
+            );
+            exclusive_start_key = output.last_evaluated_key;
+            if exclusive_start_key.is_none() || listings.len() >= per_page {
+                break;
+            }
+        }
+
+        self.sort(&mut listings);
+        Ok(MarketDbPage {
+            listings,
+            next: exclusive_start_key,
+        })
+    }
+}
+
+#[test]
+fn list_and_buy() {
+    let mut possession = Possession::new(
+        CONFIG.find_possession_handle(&"Rolling Pin").unwrap(),
+        Owner::farmer("bob".to_string()),
+    );
+    let mut bob = Wallet::default();
+    let mut alice = Wallet::default();
+
+    assert_eq!(
+        list(
+            &mut possession,
+            "alice",
+            &mut alice,
+            100,
+            "General".to_string()
+        )
+        .unwrap_err(),
+        MarketError::NotOwner("alice".to_string())
+    );
+    assert_eq!(
+        buy(&mut possession, "alice", &mut alice, &mut bob).unwrap_err(),
+        MarketError::NotListed
+    );
+    assert_eq!(
+        list(&mut possession, "bob", &mut bob, 0, "General".to_string()).unwrap_err(),
+        MarketError::PriceTooLow { min: 1 }
+    );
+    list(&mut possession, "bob", &mut bob, 100, "General".to_string()).unwrap();
+    assert_eq!(
+        list(&mut possession, "bob", &mut bob, 100, "General".to_string()).unwrap_err(),
+        MarketError::AlreadyListed
+    );
+    assert!(sale_item(&possession).is_some());
+
+    assert_eq!(
+        buy(&mut possession, "bob", &mut bob, &mut Wallet::default()).unwrap_err(),
+        MarketError::OwnListing
+    );
+    // can't afford it, so nothing changes
+    assert!(buy(&mut possession, "alice", &mut alice, &mut bob).is_err());
+    assert_eq!(possession.steader, "bob");
+    assert!(alice.ledger.is_empty() && bob.ledger.is_empty());
+
+    alice.credit(150, LedgerReason::AdminGrant("admin".to_string()));
+    let purchase = buy(&mut possession, "alice", &mut alice, &mut bob).unwrap();
+    assert_eq!(alice.gp, 50);
+    assert_eq!(bob.gp, purchase.proceeds());
+    assert_eq!(
+        purchase.fee,
+        sales_tax(CONFIG.find_market(&"General").unwrap(), 100, "bob")
+    );
+    assert_eq!(possession.steader, "alice");
+    assert_eq!(possession.sale, None);
+    assert_eq!(
+        possession.ownership_log.last().unwrap().acquisition,
+        Acquisition::Purchase { price: 100 }
+    );
+
+    list(
+        &mut possession,
+        "alice",
+        &mut alice,
+        10,
+        "General".to_string(),
+    )
+    .unwrap();
+    assert_eq!(delist(&mut possession, "alice").unwrap().price, 10);
+    assert_eq!(
+        delist(&mut possession, "alice").unwrap_err(),
+        MarketError::NotListed
+    );
+}
+
+#[test]
+fn buying_gotchis_harvests_them_for_the_seller() {
+    use crate::possess::{gotchi::HARVEST_INTERVAL, PossessionKind};
+
+    let mut possession = Possession::new(
+        CONFIG.find_possession_handle(&"Gempheus").unwrap(),
+        Owner::farmer("bob".to_string()),
+    );
+    let base = match &mut possession.kind {
+        PossessionKind::Gotchi(g) => {
+            g.last_harvest = SystemTime::now() - HARVEST_INTERVAL * 3;
+            g.happiness_updated = g.last_harvest;
+            g.base_happiness
+        }
+        _ => unreachable!(),
+    };
+    let mut bob = Wallet::default();
+    let mut alice = Wallet::default();
+    alice.credit(100, LedgerReason::AdminGrant("admin".to_string()));
+
+    list(&mut possession, "bob", &mut bob, 100, "General".to_string()).unwrap();
+    let purchase = buy(&mut possession, "alice", &mut alice, &mut bob).unwrap();
+    assert_eq!(bob.gp, purchase.proceeds() + base * 3);
+    assert_eq!(
+        bob.ledger[0].reason,
+        LedgerReason::Harvest(purchase.possession)
+    );
+
+    // alice only earns from when she bought it
+    let gotchi = possession.kind.gotchi().unwrap();
+    assert_eq!(gotchi.unharvested(purchase.at).1, 0);
+    assert_eq!(gotchi.harvest_log[0].harvested, base * 3);
+}
+
+#[test]
+fn market_search() {
+    let listed = |name, seller: &str, price| {
+        let mut p = Possession::new(
+            CONFIG.find_possession_handle(&name).unwrap(),
+            Owner::farmer(seller.to_string()),
+        );
+        list(
+            &mut p,
+            seller,
+            &mut Wallet::default(),
+            price,
+            "General".to_string(),
+        )
+        .unwrap();
+        p
+    };
+    let mut unlisted = listed("Rolling Pin", "bob", 1);
+    delist(&mut unlisted, "bob").unwrap();
+    let possessions = vec![
+        listed("Rolling Pin", "bob", 30),
+        listed("Rolling Pin", "alice", 10),
+        listed("Gempheus", "bob", 20),
+        listed("Cyl Wand", "alice", 40),
+        unlisted,
+    ];
+
+    let all = MarketQuery::default().search_possessions(&possessions);
+    assert_eq!(all.total, 4);
+    let prices: Vec<u64> = all.listings.iter().map(|l| l.sale.price).collect();
+    assert_eq!(prices, vec![10, 20, 30, 40]);
+
+    // listings of archetypes that have since left the config are skipped
+    let mut stale = Listing::of(&possessions[0]).unwrap();
+    stale.archetype_handle = CONFIG.possession_archetypes.len();
+    assert!(stale.archetype().is_none());
+    let by_name = MarketQuery {
+        sort: SortBy::Name,
+        ..Default::default()
+    };
+    assert_eq!(by_name.search(vec![stale]).total, 0);
+
+    let gotchis = MarketQuery {
+        kind: Some(KindFilter::Gotchi),
+        ..Default::default()
+    }
+    .search_possessions(&possessions);
+    assert_eq!(gotchis.total, 1);
+    assert_eq!(gotchis.listings[0].archetype().unwrap().name, "Gempheus");
+
+    let pins = MarketQuery {
+        archetype_name: Some("Rolling Pin".to_string()),
+        sort: SortBy::Priciest,
+        ..Default::default()
+    }
+    .search_possessions(&possessions);
+    assert_eq!(pins.listings[0].sale.price, 30);
+
+    let bobs_cheap = MarketQuery {
+        seller: Some("bob".to_string()),
+        max_price: Some(25),
+        ..Default::default()
+    }
+    .search_possessions(&possessions);
+    assert_eq!(bobs_cheap.total, 1);
+
+    let second_page = MarketQuery {
+        min_price: Some(15),
+        per_page: 2,
+        page: 1,
+        ..Default::default()
+    }
+    .search_possessions(&possessions);
+    assert_eq!(second_page.total, 3);
+    assert_eq!(second_page.pages, 2);
+    assert_eq!(second_page.listings.len(), 1);
+    assert_eq!(second_page.listings[0].sale.price, 40);
+
+    // no matter how far along or how big a page is asked for
+    let huge = MarketQuery {
+        page: usize::MAX,
+        per_page: usize::MAX,
+        ..Default::default()
+    }
+    .search_possessions(&possessions);
+    assert!(huge.listings.is_empty());
+    assert_eq!(huge.pages, 1);
+
+    let item = sale_item(&possessions[2]).unwrap();
+    assert_eq!(
+        Listing::from_item(&item).unwrap(),
+        Listing::of(&possessions[2]).unwrap()
+    );
+}
+
+#[test]
+fn market_search_filters_in_the_db() {
+    let gempheus = CONFIG.find_possession_handle(&"Gempheus").unwrap();
+    let (filter, values) = MarketQuery {
+        kind: Some(KindFilter::Gotchi),
+        archetype_name: Some("Gempheus".to_string()),
+        market_name: Some("General".to_string()),
+        seller: Some("bob".to_string()),
+        ..Default::default()
+    }
+    .db_filter()
+    .unwrap();
+    let filter = filter.unwrap();
+    for placeholder in values.keys().filter(|p| *p != ":sale_cat") {
+        assert!(
+            filter.contains(placeholder.as_str()),
+            "{} unused",
+            placeholder
+        );
+    }
+    assert_eq!(values[":archetype_handle"].n, Some(gempheus.to_string()));
+    assert!(values.contains_key(&format!(":kind_{}", gempheus)));
+    assert!(!values.contains_key(&format!(
+        ":kind_{}",
+        CONFIG.find_possession_handle(&"Rolling Pin").unwrap()
+    )));
+
+    // nothing could match an archetype that isn't in the config
+    assert!(MarketQuery {
+        archetype_name: Some("Not A Thing".to_string()),
+        ..Default::default()
+    }
+    .db_filter()
+    .is_none());
+    assert_eq!(MarketQuery::default().db_filter().unwrap().0, None);
+}
+
+#[test]
+fn price_history_buckets() {
+    let day = Duration::from_secs(60 * 60 * 24);
+    let start = SystemTime::UNIX_EPOCH + day * 365 * 50;
+    let pin = CONFIG.find_possession_handle(&"Rolling Pin").unwrap();
+    let wand = CONFIG.find_possession_handle(&"Cyl Wand").unwrap();
+    let event = |archetype_handle, price, at| SaleEvent {
+        id: uuid::Uuid::new_v4(),
+        archetype_handle,
+        price,
+        at,
+        market_name: "General".to_string(),
+    };
+    let events = vec![
+        event(pin, 10, start),
+        event(pin, 30, start + day / 2),
+        event(pin, 20, start + day / 4),
+        event(wand, 1000, start),
+        event(pin, 50, start + day * 3),
+    ];
+
+    let history = price_history(&events, pin, day);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].start, start);
+    assert_eq!(
+        history[0].stats,
+        PriceStats {
+            count: 3,
+            min: 10,
+            median: 20,
+            max: 30
+        }
+    );
+    assert_eq!(history[1].start, start + day * 3);
+    assert_eq!(PriceStats::of(vec![10, 20, 30, 40]).unwrap().median, 25);
+    assert_eq!(
+        PriceStats::of(vec![u64::MAX, u64::MAX - 2]).unwrap().median,
+        u64::MAX - 1
+    );
+    assert_eq!(PriceStats::of(vec![]), None);
+
+    let stored = &events[1];
+    assert_eq!(&SaleEvent::from_item(&stored.item()).unwrap(), stored);
+    // an archetype's events are keyed together, and in order of when they happened
+    let key = |e: &SaleEvent| e.key_item()["id"].s.clone().unwrap();
+    assert!(key(&events[0]).starts_with(&format!("{}#", pin)));
+    assert!(key(&events[0]) < key(&events[2]) && key(&events[2]) < key(&events[1]));
+
+    // purchases from the market show up in ownership logs
+    let mut possession = Possession::new(pin, Owner::farmer("bob".to_string()));
+    let mut alice = Wallet::default();
+    alice.credit(100, LedgerReason::AdminGrant("admin".to_string()));
+    list(
+        &mut possession,
+        "bob",
+        &mut Wallet::default(),
+        60,
+        "General".to_string(),
+    )
+    .unwrap();
+    let purchase = buy(&mut possession, "alice", &mut alice, &mut Wallet::default()).unwrap();
+    assert_eq!(
+        PriceStats::logged(vec![&possession], pin),
+        Some(PriceStats {
+            count: 1,
+            min: 60,
+            median: 60,
+            max: 60
+        })
+    );
+    assert_eq!(purchase.event().price, 60);
+    assert_eq!(purchase.event().archetype_handle, pin);
+}
+
+#[test]
+fn market_rules() {
+    let exchange = CONFIG.find_market(&"Gotchi Exchange").unwrap().clone();
+    let possession = |name| {
+        Possession::new(
+            CONFIG.find_possession_handle(&name).unwrap(),
+            Owner::farmer("bob".to_string()),
+        )
+    };
+    let mut bob = Wallet::default();
+
+    let mut pin = possession("Rolling Pin");
+    assert_eq!(
+        list(&mut pin, "bob", &mut bob, 100, "Nowhere".to_string()).unwrap_err(),
+        MarketError::UnknownMarket("Nowhere".to_string())
+    );
+    assert_eq!(
+        list(&mut pin, "bob", &mut bob, 100, exchange.name.clone()).unwrap_err(),
+        MarketError::KindNotAllowed {
+            market: exchange.name.clone(),
+            archetype: "Rolling Pin".to_string(),
+        }
+    );
+
+    let mut gotchi = possession("Gempheus");
+    let too_low = exchange.min_price - 1;
+    assert_eq!(
+        list(&mut gotchi, "bob", &mut bob, too_low, exchange.name.clone()).unwrap_err(),
+        MarketError::PriceTooLow {
+            min: exchange.min_price
+        }
+    );
+    let max = exchange.max_price.unwrap();
+    assert_eq!(
+        list(&mut gotchi, "bob", &mut bob, max + 1, exchange.name.clone()).unwrap_err(),
+        MarketError::PriceTooHigh { max }
+    );
+
+    // the seller has to be able to afford the listing fee
+    assert!(list(&mut gotchi, "bob", &mut bob, max, exchange.name.clone()).is_err());
+    assert_eq!(gotchi.sale, None);
+    bob.credit(
+        exchange.listing_fee,
+        LedgerReason::AdminGrant("admin".to_string()),
+    );
+    // which comes out of their wallet right away
+    let fee = list(&mut gotchi, "bob", &mut bob, max, exchange.name.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(fee.amount, exchange.listing_fee);
+    assert_eq!(fee.reason, LedgerReason::ListingFee(gotchi.id));
+    assert_eq!(bob.gp, 0);
+    // but listing somewhere free doesn't charge anything
+    assert_eq!(
+        list(&mut pin, "bob", &mut bob, 100, "General".to_string()).unwrap(),
+        None
+    );
+    assert_eq!(
+        sales_tax(&exchange, u64::MAX, "bob"),
+        u64::MAX / 100 * exchange.sales_tax_percent
+    );
+    assert_eq!(
+        Purchase::of(&gotchi, "alice").unwrap().fee,
+        max * exchange.sales_tax_percent / 100
+    );
 }
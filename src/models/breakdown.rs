@@ -0,0 +1,242 @@
+//! Explains where each of a plant's bonuses come from,
+//! so that players can find out why their plant grows the way it does.
+use super::Hacksteader;
+use crate::config::{ConfigError, PlantAdvancement, PlantAdvancementKind, PlantAdvancementSum};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Where an advancement affecting a plant came from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum EffectSource {
+    /// Unlocked by the plant itself gaining xp.
+    Level,
+    /// Contains: the id of the tile with the plant giving out this bonus
+    Neighbor(uuid::Uuid),
+    /// Contains: the id of the gotchi
+    Gotchi(uuid::Uuid),
+    /// Contains: the id of the keepsake
+    Keepsake(uuid::Uuid),
+    /// Contains: the id of the possession which was applied to the plant
+    AppliedItem(uuid::Uuid),
+}
+
+/// The stats of a plant which advancements change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum PlantStat {
+    ExtraTimeTicks,
+    TimeTicksMultiplier,
+    Xp,
+    YieldSpeedMultiplier,
+    YieldSizeMultiplier,
+    /// How many kinds of yields the plant has.
+    Yields,
+    CraftSpeedMultiplier,
+    CraftReturnChance,
+    DoubleCraftYieldChance,
+    /// How many recipes the plant knows.
+    Recipes,
+    /// How many seeds the plant is expected to produce each time it yields.
+    SeedYields,
+}
+impl PlantStat {
+    /// The stat an advancement of this kind changes, and by how much.
+    pub fn of(kind: &PlantAdvancementKind) -> (Self, f32) {
+        use PlantAdvancementKind::*;
+
+        match kind {
+            Neighbor(inner) | Pedigree(_, inner) => Self::of(inner),
+            &ExtraTimeTicks(extra) => (PlantStat::ExtraTimeTicks, extra as f32),
+            &TimeTicksMultiplier(m) => (PlantStat::TimeTicksMultiplier, m),
+            &Xp(xp) => (PlantStat::Xp, xp),
+            &YieldSpeedMultiplier(m) => (PlantStat::YieldSpeedMultiplier, m),
+            &YieldSizeMultiplier(m) => (PlantStat::YieldSizeMultiplier, m),
+            Yield(yields) => (PlantStat::Yields, yields.len() as f32),
+            &CraftSpeedMultiplier(m) => (PlantStat::CraftSpeedMultiplier, m),
+            &CraftReturnChance(chance) => (PlantStat::CraftReturnChance, chance),
+            &DoubleCraftYield(chance) => (PlantStat::DoubleCraftYieldChance, chance),
+            Craft(recipes) => (PlantStat::Recipes, recipes.len() as f32),
+            SeedYield(cp) => (PlantStat::SeedYields, cp.expected_count()),
+        }
+    }
+
+    /// Multipliers stack by multiplying together, everything else adds up.
+    pub fn is_multiplier(self) -> bool {
+        use PlantStat::*;
+
+        match self {
+            TimeTicksMultiplier | YieldSpeedMultiplier | YieldSizeMultiplier
+            | CraftSpeedMultiplier => true,
+            _ => false,
+        }
+    }
+}
+
+/// A single advancement affecting a plant, and what it does to the plant's stats.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Contribution {
+    pub source: EffectSource,
+    pub advancement: PlantAdvancement,
+    pub stat: PlantStat,
+    /// How much this advancement changes the stat by on its own.
+    pub amount: f32,
+    /// The stat's value once this and every contribution before it apply.
+    pub total: f32,
+}
+
+/// Every advancement affecting the plant on a tile,
+/// in the order they're folded into its advancements sum.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Breakdown {
+    pub tile_id: uuid::Uuid,
+    pub plant: String,
+    pub contributions: Vec<Contribution>,
+    pub sum: PlantAdvancementSum,
+}
+
+impl Hacksteader {
+    /// Explains the bonuses of the plant on the tile with the given id at the given time,
+    /// or returns None if there's no plant there.
+    pub fn breakdown(
+        &self,
+        tile_id: uuid::Uuid,
+        now: SystemTime,
+    ) -> Result<Option<Breakdown>, ConfigError> {
+        let index = match self.land.iter().position(|t| t.id == tile_id) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let plant = match self.land[index].plant.as_ref() {
+            Some(plant) => plant,
+            None => return Ok(None),
+        };
+
+        let own = plant
+            .advancements
+            .unlocked(plant.xp)
+            .filter(|a| match a.kind {
+                PlantAdvancementKind::Neighbor(..) => false,
+                _ => true,
+            })
+            .map(|a| (EffectSource::Level, Cow::Borrowed(a)));
+        let neighbors = self
+            .sourced_neighbor_advancements()
+            .swap_remove(index)
+            .into_iter()
+            .map(|(tile_id, a)| (EffectSource::Neighbor(tile_id), Cow::Borrowed(a)));
+        let items = self.sourced_item_advancements(plant.archetype_handle, now)?;
+        let effects = plant.effects.iter().map(|e| {
+            (
+                EffectSource::AppliedItem(e.source),
+                Cow::Borrowed(&e.advancement),
+            )
+        });
+
+        let generations = plant.generations();
+        let mut totals: HashMap<PlantStat, f32> = HashMap::new();
+        let contributions =
+            own.chain(neighbors)
+                .chain(items)
+                .chain(effects)
+                .filter_map(|(source, advancement)| {
+                    Some((
+                        source,
+                        super::plant::for_generations(advancement, generations)?,
+                    ))
+                })
+                .map(|(source, advancement)| {
+                    let (stat, amount) = PlantStat::of(&advancement.kind);
+                    let total = totals.entry(stat).or_insert_with(|| {
+                        if stat.is_multiplier() {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    });
+                    if stat.is_multiplier() {
+                        *total *= amount;
+                    } else {
+                        *total += amount;
+                    }
+
+                    Contribution {
+                        source,
+                        advancement: advancement.into_owned(),
+                        stat,
+                        amount,
+                        total: *total,
+                    }
+                })
+                .collect();
+
+        Ok(Some(Breakdown {
+            tile_id,
+            plant: plant.name.clone(),
+            contributions,
+            sum: plant.advancements_sum(&self.plant_contexts(now)?[index]),
+        }))
+    }
+}
+
+#[test]
+fn breakdown_explains_every_bonus() {
+    use super::{test_hacksteader, test_tile};
+    use crate::{possess::Owner, Possession, CONFIG};
+
+    let mut hs = test_hacksteader(vec![test_tile(Some("Bractus")), test_tile(Some("Bractus"))]);
+    for tile in hs.land.iter_mut() {
+        let plant = tile.plant.as_mut().unwrap();
+        plant.xp = plant.advancements.required_xp(3).unwrap();
+    }
+    hs.inventory.push(Possession::new(
+        CONFIG.find_possession_handle(&"Rolling Pin").unwrap(),
+        Owner::farmer("bob".to_string()),
+    ));
+
+    let tile_id = hs.land[0].id;
+    let now = SystemTime::now();
+    let breakdown = hs.breakdown(tile_id, now).unwrap().unwrap();
+    let sum = hs.plant_sums(now).unwrap()[0].clone().unwrap();
+    assert_eq!(breakdown.sum, sum);
+
+    let sources = |f: fn(&EffectSource) -> bool| {
+        breakdown
+            .contributions
+            .iter()
+            .filter(|c| f(&c.source))
+            .count()
+    };
+    assert!(sources(|s| *s == EffectSource::Level) > 0);
+    assert_eq!(
+        sources(|s| match s {
+            EffectSource::Keepsake(_) => true,
+            _ => false,
+        }),
+        1
+    );
+    assert!(breakdown.contributions.iter().all(|c| match c.source {
+        EffectSource::Neighbor(id) => id == hs.land[1].id,
+        _ => true,
+    }));
+
+    // the last running total of each multiplier is the one in the sum
+    let last_total = |stat| {
+        breakdown
+            .contributions
+            .iter()
+            .rev()
+            .find(|c| c.stat == stat)
+            .map(|c| c.total)
+            .unwrap_or(1.0)
+    };
+    assert!(
+        (last_total(PlantStat::YieldSpeedMultiplier) - sum.yield_speed_multiplier).abs() < 0.001
+    );
+    assert!((last_total(PlantStat::YieldSizeMultiplier) - sum.yield_size_multiplier).abs() < 0.001);
+    assert!(
+        (last_total(PlantStat::CraftSpeedMultiplier) - sum.crafting_speed_multiplier).abs() < 0.001
+    );
+
+    assert!(hs.breakdown(uuid::Uuid::new_v4(), now).unwrap().is_none());
+}
@@ -0,0 +1,197 @@
+use super::{Hacksteader, Tile};
+use crate::CONFIG;
+use serde::Serialize;
+use std::fmt;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum LandError {
+    /// Contains: the id of the deed which couldn't be found in the inventory
+    NoSuchDeed(uuid::Uuid),
+    /// Contains: the name of the item which doesn't unlock land
+    NotADeed(String),
+    OnSale,
+    /// This deed requires xp, and the Hacksteader already has as much land as they've earned.
+    NotEnoughXp {
+        land: usize,
+        allowed: u32,
+    },
+}
+impl fmt::Display for LandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use LandError::*;
+        match self {
+            NoSuchDeed(id) => write!(f, "no deed with the id {} in this inventory", id),
+            NotADeed(name) => write!(f, "{} can't be redeemed for land", name),
+            OnSale => write!(f, "deeds can't be redeemed while they're up for sale"),
+            NotEnoughXp { land, allowed } => write!(
+                f,
+                "you have {} pieces of land, and need more xp to unlock more than {}",
+                land, allowed
+            ),
+        }
+    }
+}
+
+impl Hacksteader {
+    /// How many pieces of land this Hacksteader has earned with xp.
+    /// Only land which wasn't granted counts against this.
+    pub fn land_allowance(&self) -> u32 {
+        CONFIG
+            .profile_archetype
+            .advancements
+            .sum(self.profile.xp, std::iter::empty())
+            .land
+    }
+
+    /// Uses up the deed with the given id from this Hacksteader's inventory to add a new Tile
+    /// to their land. Deeds which require xp can only be redeemed while the Hacksteader
+    /// has less land than they've earned; those which don't, like Land Grants,
+    /// can always be redeemed, and the land they give doesn't count against what's earned.
+    pub fn redeem_land(
+        &mut self,
+        deed_id: uuid::Uuid,
+        now: SystemTime,
+    ) -> Result<&Tile, LandError> {
+        let index = self
+            .inventory
+            .iter()
+            .position(|p| p.id == deed_id)
+            .ok_or(LandError::NoSuchDeed(deed_id))?;
+        let deed = &self.inventory[index];
+
+        let unlock = deed
+            .kind
+            .keepsake()
+            .and_then(|k| k.unlocks_land.as_ref())
+            .ok_or_else(|| LandError::NotADeed(deed.name.clone()))?;
+        if deed.sale.is_some() {
+            return Err(LandError::OnSale);
+        }
+
+        let requires_xp = unlock.requires_xp;
+        let allowed = self.land_allowance();
+        let earned = self.land.iter().filter(|t| !t.granted).count();
+        if requires_xp && earned >= allowed as usize {
+            return Err(LandError::NotEnoughXp {
+                land: earned,
+                allowed,
+            });
+        }
+
+        self.inventory.remove(index);
+        self.land.push(Tile {
+            acquired: now,
+            plant: None,
+            id: uuid::Uuid::new_v4(),
+            steader: self.user_id.clone(),
+            granted: !requires_xp,
+        });
+        Ok(self.land.last().unwrap())
+    }
+}
+
+#[test]
+fn deeds_and_grants() {
+    use super::{test_hacksteader, test_tile};
+    use crate::{possess::Owner, Possession};
+
+    let deed = |name| {
+        Possession::new(
+            CONFIG.find_possession_handle(&name).unwrap(),
+            Owner::farmer("bob".to_string()),
+        )
+    };
+    let mut hs = test_hacksteader(vec![]);
+    let allowed = hs.land_allowance() as usize;
+    hs.land = (0..allowed).map(|_| test_tile(None)).collect();
+
+    let land_deed = deed("Land Deed");
+    hs.inventory.push(land_deed.clone());
+    assert_eq!(
+        hs.redeem_land(land_deed.id, SystemTime::now()).unwrap_err(),
+        LandError::NotEnoughXp {
+            land: allowed,
+            allowed: allowed as u32
+        }
+    );
+
+    let land_grant = deed("Land Grant");
+    hs.inventory.push(land_grant.clone());
+    assert!(
+        hs.redeem_land(land_grant.id, SystemTime::now())
+            .unwrap()
+            .granted
+    );
+    assert_eq!(hs.land.len(), allowed + 1);
+    assert_eq!(
+        hs.redeem_land(land_grant.id, SystemTime::now())
+            .unwrap_err(),
+        LandError::NoSuchDeed(land_grant.id)
+    );
+
+    let not_a_deed = deed("Rolling Pin");
+    hs.inventory.push(not_a_deed.clone());
+    assert_eq!(
+        hs.redeem_land(not_a_deed.id, SystemTime::now())
+            .unwrap_err(),
+        LandError::NotADeed("Rolling Pin".to_string())
+    );
+
+    // enough xp for the next advancement, which grants more land
+    hs.profile.xp = CONFIG
+        .profile_archetype
+        .advancements
+        .required_xp(1)
+        .unwrap();
+    assert!(hs.land_allowance() as usize > hs.land.len());
+    assert!(
+        !hs.redeem_land(land_deed.id, SystemTime::now())
+            .unwrap()
+            .granted
+    );
+    assert_eq!(hs.land.len(), allowed + 2);
+    assert!(hs.inventory.iter().all(|p| p.id != land_deed.id));
+}
+
+#[test]
+fn grant_then_deed() {
+    use super::{test_hacksteader, test_tile};
+    use crate::{possess::Owner, Possession};
+
+    let deed = |name| {
+        Possession::new(
+            CONFIG.find_possession_handle(&name).unwrap(),
+            Owner::farmer("bob".to_string()),
+        )
+    };
+    let mut hs = test_hacksteader(vec![]);
+    hs.profile.xp = CONFIG
+        .profile_archetype
+        .advancements
+        .required_xp(1)
+        .unwrap();
+    let allowed = hs.land_allowance() as usize;
+    hs.land = (1..allowed).map(|_| test_tile(None)).collect();
+
+    // room for one more earned tile, which the grant shouldn't take up
+    let land_grant = deed("Land Grant");
+    hs.inventory.push(land_grant.clone());
+    hs.redeem_land(land_grant.id, SystemTime::now()).unwrap();
+
+    let land_deed = deed("Land Deed");
+    hs.inventory.push(land_deed.clone());
+    hs.redeem_land(land_deed.id, SystemTime::now()).unwrap();
+    assert_eq!(hs.land.len(), allowed + 1);
+
+    let another_deed = deed("Land Deed");
+    hs.inventory.push(another_deed.clone());
+    assert_eq!(
+        hs.redeem_land(another_deed.id, SystemTime::now())
+            .unwrap_err(),
+        LandError::NotEnoughXp {
+            land: allowed,
+            allowed: allowed as u32
+        }
+    );
+}
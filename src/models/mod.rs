@@ -0,0 +1,763 @@
+use crate::*;
+use config::{
+    ArchetypeHandle, ArchetypeKind, ConfigError, ItemApplicationEffect, ItemApplicationEffectKind,
+    PlantAdvancement, PlantAdvancementKind, PlantAdvancementSum, SelectivePlantAdvancement,
+};
+use currency::Wallet;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+pub mod breakdown;
+pub mod land;
+pub mod plant;
+
+pub use breakdown::{Breakdown, Contribution, EffectSource, PlantStat};
+pub use land::LandError;
+pub use plant::{Craft, CraftError, CraftOutcome, Effect, Plant, PlantContext, PlantEvent};
+
+/// No more than this much time is caught up on cycle by cycle when a Hacksteader returns;
+/// anything before that is skipped over all at once with `Plant::skip`,
+/// so that the work done to catch up stays bounded no matter how long they were away.
+pub const MAX_CATCH_UP: Duration = Duration::from_secs(60 * 60 * 24 * 90);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Hacksteader {
+    pub user_id: String,
+    pub profile: Profile,
+    pub land: Vec<Tile>,
+    pub inventory: Vec<Possession>,
+    pub gotchis: Vec<Possessed<possess::Gotchi>>,
+}
+
+/// Something that happened to a plant on one of a Hacksteader's tiles.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FarmEvent {
+    pub at: SystemTime,
+    pub tile_id: uuid::Uuid,
+    pub event: PlantEvent,
+}
+
+/// Everything that happened on a hackstead while its owner was away.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatchUp {
+    /// How many whole farm cycles were caught up on.
+    pub cycles: u64,
+    /// How much of the time since `last_farm` was more than `MAX_CATCH_UP` ago,
+    /// and so was skipped over all at once.
+    pub skipped: Duration,
+    /// Sorted by when they happened.
+    pub events: Vec<FarmEvent>,
+}
+
+impl Hacksteader {
+    /// For each tile, the `Neighbor` advancements unlocked by the plants of the same archetype
+    /// on every other tile, alongside the id of the tile each came from.
+    /// Tiles without plants get none.
+    pub fn sourced_neighbor_advancements(
+        &self,
+    ) -> Vec<Vec<(uuid::Uuid, &'static PlantAdvancement)>> {
+        let unlocked: Vec<Vec<&'static PlantAdvancement>> = self
+            .land
+            .iter()
+            .map(|tile| {
+                tile.plant
+                    .as_ref()
+                    .and_then(|p| Some((p.xp, CONFIG.plant_archetypes.get(p.archetype_handle)?)))
+                    .map(|(xp, archetype)| {
+                        archetype
+                            .advancements
+                            .unlocked(xp)
+                            .filter(|a| match a.kind {
+                                PlantAdvancementKind::Neighbor(..) => true,
+                                _ => false,
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        (0..self.land.len())
+            .map(|i| {
+                unlocked
+                    .iter()
+                    .zip(self.land.iter())
+                    .enumerate()
+                    .filter(|&(other, (_, tile))| {
+                        let archetype = |t: &Tile| t.plant.as_ref().map(|p| p.archetype_handle);
+                        other != i
+                            && archetype(tile).is_some()
+                            && archetype(tile) == archetype(&self.land[i])
+                    })
+                    .flat_map(|(_, (advancements, tile))| {
+                        advancements.iter().map(move |&a| (tile.id, a))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// For each tile, the `Neighbor` advancements unlocked by the plants of the same archetype
+    /// on every other tile.
+    pub fn neighbor_advancements(&self) -> Vec<Vec<&'static PlantAdvancement>> {
+        self.sourced_neighbor_advancements()
+            .into_iter()
+            .map(|n| n.into_iter().map(|(_, a)| a).collect())
+            .collect()
+    }
+
+    /// The advancements a plant of the given archetype gets from the gotchis and keepsakes
+    /// this Hacksteader owns, alongside the item each came from. Only one item of each kind counts,
+    /// so that hoarding copies of an item doesn't make it any stronger; for gotchis,
+    /// that's the happiest one, since gotchis' advancements are as strong as the gotchis
+    /// are happy at the given time. Items which are up for sale don't help.
+    pub fn sourced_item_advancements(
+        &self,
+        plant: ArchetypeHandle,
+        now: SystemTime,
+    ) -> Result<Vec<(EffectSource, Cow<'static, PlantAdvancement>)>, ConfigError> {
+        let owned = self
+            .inventory
+            .iter()
+            .map(|p| (p.archetype_handle, p.id, p.kind.gotchi(), p.sale.is_some()))
+            .chain(
+                self.gotchis
+                    .iter()
+                    .map(|g| (g.archetype_handle, g.id, Some(&g.inner), g.sale.is_some())),
+            );
+        let mut strongest: Vec<(ArchetypeHandle, uuid::Uuid, f32)> = vec![];
+        for (ah, id, gotchi, on_sale) in owned {
+            if on_sale {
+                continue;
+            }
+            let strength = gotchi.map(|g| g.plant_effect_strength(now)).unwrap_or(1.0);
+            match strongest.iter_mut().find(|(kind, _, _)| *kind == ah) {
+                Some(item) if item.2 < strength => *item = (ah, id, strength),
+                Some(_) => {}
+                None => strongest.push((ah, id, strength)),
+            }
+        }
+
+        let mut advancements = vec![];
+        for (ah, id, strength) in strongest {
+            let (source, effects): (EffectSource, &'static [SelectivePlantAdvancement]) =
+                match CONFIG.possession_archetypes.get(ah).map(|a| &a.kind) {
+                    Some(ArchetypeKind::Gotchi(g)) => (EffectSource::Gotchi(id), &g.plant_effects),
+                    Some(ArchetypeKind::Keepsake(k)) => {
+                        (EffectSource::Keepsake(id), &k.plant_effects)
+                    }
+                    _ => continue,
+                };
+            for e in effects {
+                if !e.keep_plants.lookup_handles()?.allows(&plant) {
+                    continue;
+                }
+                let advancement = if strength == 1.0 {
+                    Cow::Borrowed(&e.advancement)
+                } else {
+                    Cow::Owned(PlantAdvancement {
+                        kind: e.advancement.kind.scaled(strength),
+                        ..e.advancement.clone()
+                    })
+                };
+                advancements.push((source.clone(), advancement));
+            }
+        }
+        Ok(advancements)
+    }
+
+    /// The advancements a plant of the given archetype gets from
+    /// the gotchis and keepsakes this Hacksteader owns at the given time.
+    pub fn item_advancements(
+        &self,
+        plant: ArchetypeHandle,
+        now: SystemTime,
+    ) -> Result<Vec<Cow<'static, PlantAdvancement>>, ConfigError> {
+        Ok(self
+            .sourced_item_advancements(plant, now)?
+            .into_iter()
+            .map(|(_, a)| a)
+            .collect())
+    }
+
+    /// The context each tile's plant grows in,
+    /// given the plants around it and the items this Hacksteader owns at the given time.
+    pub fn plant_contexts(
+        &self,
+        now: SystemTime,
+    ) -> Result<Vec<PlantContext<'static>>, ConfigError> {
+        self.land
+            .iter()
+            .zip(self.neighbor_advancements())
+            .map(|(tile, neighbors)| {
+                let mut extra_advancements: Vec<_> =
+                    neighbors.into_iter().map(Cow::Borrowed).collect();
+                if let Some(plant) = &tile.plant {
+                    extra_advancements.extend(self.item_advancements(plant.archetype_handle, now)?);
+                }
+                Ok(PlantContext {
+                    extra_advancements,
+                    grower: Some(self.user_id.clone()),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// All of the bonuses the plant on each tile currently has,
+    /// including those from its neighbors, at the given time.
+    pub fn plant_sums(
+        &self,
+        now: SystemTime,
+    ) -> Result<Vec<Option<PlantAdvancementSum>>, ConfigError> {
+        Ok(self
+            .land
+            .iter()
+            .zip(self.plant_contexts(now)?)
+            .map(|(tile, ctx)| tile.plant.as_ref().map(|p| p.advancements_sum(&ctx)))
+            .collect())
+    }
+
+    /// Grows every plant on this hackstead from `profile.last_farm` up to `now`,
+    /// moving `last_farm` forward by however many whole farm cycles fit in between.
+    /// Tiles acquired after `last_farm` only grow from when they were acquired,
+    /// and plants which are destroyed by crafts are removed from their tiles.
+    /// Bonuses from neighbors are those the plants had before catching up.
+    /// Anything more than `MAX_CATCH_UP` ago is skipped over with `Plant::skip`, and how much
+    /// was skipped is reported in `CatchUp::skipped`. Nothing changes if an item
+    /// helping the plants has misconfigured plant effects.
+    pub fn catch_up(
+        &mut self,
+        now: SystemTime,
+        rng: &mut impl rand::Rng,
+    ) -> Result<CatchUp, ConfigError> {
+        let contexts = self.plant_contexts(now)?;
+        let farm_cycle = PlantContext::default().farm_cycle.as_millis().max(1);
+        let cycles_time = |n: u64| Duration::from_millis((farm_cycle as u64) * n);
+
+        let last_farm = self.profile.last_farm;
+        let elapsed = now.duration_since(last_farm).unwrap_or_default();
+        let all_cycles = (elapsed.as_millis() / farm_cycle) as u64;
+        let cycles = all_cycles.min((MAX_CATCH_UP.as_millis() / farm_cycle) as u64);
+        let skipped_cycles = all_cycles - cycles;
+        let from = last_farm + cycles_time(skipped_cycles);
+
+        let mut events = vec![];
+        for (tile, ctx) in self.land.iter_mut().zip(contexts) {
+            let tile_id = tile.id;
+            let missed = tile.acquired.duration_since(last_farm).unwrap_or_default();
+            let tile_skipped =
+                skipped_cycles.saturating_sub((missed.as_millis() / farm_cycle) as u64);
+            if let Some(plant) = &mut tile.plant {
+                let skipped = plant.skip(tile_skipped, &ctx, rng);
+                let destroyed = skipped.iter().any(|e| match e {
+                    PlantEvent::CraftFinished(outcome) => outcome.destroyed_plant,
+                    _ => false,
+                });
+                if destroyed {
+                    tile.plant = None;
+                }
+                events.extend(skipped.into_iter().map(|event| FarmEvent {
+                    at: from,
+                    tile_id,
+                    event,
+                }));
+            }
+
+            let start = tile.acquired.max(from);
+            let skipped = start.duration_since(from).unwrap_or_default();
+            let tile_cycles = cycles.saturating_sub((skipped.as_millis() / farm_cycle) as u64);
+
+            let plant = match &mut tile.plant {
+                Some(plant) => plant,
+                None => continue,
+            };
+            let timed = plant.grow_timed(tile_cycles, &ctx, rng);
+
+            let destroyed = timed.iter().any(|(_, e)| match e {
+                PlantEvent::CraftFinished(outcome) => outcome.destroyed_plant,
+                _ => false,
+            });
+            if destroyed {
+                tile.plant = None;
+            }
+
+            let end = from + cycles_time(cycles);
+            events.extend(timed.into_iter().map(|(at, event)| FarmEvent {
+                // counted back from the end so tiles acquired late still line up
+                at: end - cycles_time(tile_cycles - at),
+                tile_id,
+                event,
+            }));
+        }
+        events.sort_by_key(|e| e.at);
+
+        self.profile.last_farm = from + cycles_time(cycles);
+        Ok(CatchUp {
+            cycles,
+            skipped: cycles_time(skipped_cycles),
+            events,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ItemApplyError {
+    /// Contains: the name of the item which can't be applied to plants
+    NotApplicable(String),
+    OnSale,
+    /// Either the item or the tile belongs to someone else
+    NotOwned,
+    NoPlant,
+    /// None of the item's effects work on this kind of plant
+    PlantNotAllowed,
+    Config(ConfigError),
+}
+impl fmt::Display for ItemApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ItemApplyError::*;
+        match self {
+            NotApplicable(name) => write!(f, "{} can't be applied to plants", name),
+            OnSale => write!(f, "items can't be applied while they're up for sale"),
+            NotOwned => write!(f, "items can only be applied to your own plants"),
+            NoPlant => write!(f, "there's no plant on this tile to apply the item to"),
+            PlantNotAllowed => write!(f, "this item has no effect on this kind of plant"),
+            Config(e) => write!(f, "item has a misconfigured effect: {}", e),
+        }
+    }
+}
+impl From<ConfigError> for ItemApplyError {
+    fn from(e: ConfigError) -> Self {
+        ItemApplyError::Config(e)
+    }
+}
+
+/// Has the given steader apply one of their items to the plant on one of their tiles,
+/// returning the effects which took hold. Effects whose `keep_plants` rule out the plant
+/// are skipped; the item is used up, so callers should delete it.
+/// If any effect can't be applied, none are.
+pub fn apply_item(
+    steader: &str,
+    item: &Possession,
+    tile: &mut Tile,
+) -> Result<Vec<&'static ItemApplicationEffect>, ItemApplyError> {
+    let application = CONFIG
+        .possession_archetypes
+        .get(item.archetype_handle)
+        .and_then(|a| a.kind.keepsake())
+        .and_then(|k| k.item_application.as_ref())
+        .ok_or_else(|| ItemApplyError::NotApplicable(item.name.clone()))?;
+    if item.sale.is_some() {
+        return Err(ItemApplyError::OnSale);
+    }
+    if item.steader != steader || tile.steader != steader {
+        return Err(ItemApplyError::NotOwned);
+    }
+    let plant = tile.plant.as_mut().ok_or(ItemApplyError::NoPlant)?;
+
+    // work out what every effect does before any of them change the plant
+    let mut plant_handle = plant.archetype_handle;
+    let mut applicable = vec![];
+    for (effect_archetype_handle, effect) in application.effects.iter().enumerate() {
+        // earlier effects may have turned the plant into something else
+        if !effect.keep_plants.lookup_handles()?.allows(&plant_handle) {
+            continue;
+        }
+        let turns_into = match &effect.kind {
+            ItemApplicationEffectKind::TurnsPlantInto(name) => {
+                plant_handle = CONFIG.find_plant_handle(name)?;
+                Some(plant_handle)
+            }
+            ItemApplicationEffectKind::PlantAdvancement(_) => None,
+        };
+        applicable.push((effect_archetype_handle, effect, turns_into));
+    }
+
+    let mut applied = vec![];
+    for (effect_archetype_handle, effect, turns_into) in applicable {
+        match &effect.kind {
+            ItemApplicationEffectKind::PlantAdvancement(advancement) => plant.add_effect(
+                Effect {
+                    source: item.id,
+                    until_finish: effect.duration,
+                    item_archetype_handle: item.archetype_handle,
+                    effect_archetype_handle,
+                    advancement: advancement.clone(),
+                },
+                effect.stacking,
+            ),
+            ItemApplicationEffectKind::TurnsPlantInto(_) => {
+                if let Some(plant_handle) = turns_into {
+                    plant.archetype_handle = plant_handle;
+                }
+                plant.until_yield = plant.base_yield_duration.unwrap_or(0.0);
+            }
+        }
+        applied.push(effect);
+    }
+
+    if applied.is_empty() {
+        Err(ItemApplyError::PlantNotAllowed)
+    } else {
+        Ok(applied)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PlantSeedError {
+    /// There's already a plant on this tile
+    Occupied,
+    OnSale,
+    UnknownPlant(ConfigError),
+}
+impl fmt::Display for PlantSeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use PlantSeedError::*;
+        match self {
+            Occupied => write!(f, "there's already a plant on this tile"),
+            OnSale => write!(f, "seeds can't be planted while they're up for sale"),
+            UnknownPlant(e) => write!(f, "seed grows into something unknown: {}", e),
+        }
+    }
+}
+impl From<ConfigError> for PlantSeedError {
+    fn from(e: ConfigError) -> Self {
+        PlantSeedError::UnknownPlant(e)
+    }
+}
+
+/// Plants a seed onto an empty tile, using the seed up, so callers should delete it.
+/// The new plant inherits the seed's pedigree.
+pub fn plant_seed(
+    seed: Possessed<possess::Seed>,
+    tile: &mut Tile,
+) -> Result<&Plant, PlantSeedError> {
+    if tile.plant.is_some() {
+        return Err(PlantSeedError::Occupied);
+    }
+    if seed.sale.is_some() {
+        return Err(PlantSeedError::OnSale);
+    }
+
+    let archetype_handle = CONFIG.find_plant_handle(&seed.inner.grows_into)?;
+    Ok(tile
+        .plant
+        .get_or_insert(Plant::new(archetype_handle, seed.inner.pedigree)))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tile {
+    pub acquired: SystemTime,
+    pub plant: Option<Plant>,
+    pub id: uuid::Uuid,
+    pub steader: String,
+    /// Whether this Tile came from a deed that doesn't require xp,
+    /// in which case it doesn't count against the land the Hacksteader has earned.
+    pub granted: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Profile {
+    /// Indicates when this Hacksteader first joined the elite community.
+    pub joined: SystemTime,
+    pub last_active: SystemTime,
+    pub last_farm: SystemTime,
+    /// This is not an uuid::Uuid because it's actually the steader id of the person who owns this Profile
+    pub id: String,
+    pub xp: u64,
+    pub wallet: Wallet,
+}
+
+#[test]
+fn catch_up_is_bounded_and_ordered() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let now = SystemTime::now();
+    let long_ago = now - MAX_CATCH_UP * 2;
+    let tile = |acquired| Tile {
+        acquired,
+        plant: Some(Plant::new(
+            CONFIG.find_plant_handle(&"Bractus").unwrap(),
+            vec![],
+        )),
+        id: uuid::Uuid::new_v4(),
+        steader: "bob".to_string(),
+        granted: false,
+    };
+
+    let mut hs = Hacksteader {
+        user_id: "bob".to_string(),
+        profile: Profile {
+            joined: long_ago,
+            last_active: long_ago,
+            last_farm: long_ago,
+            id: "bob".to_string(),
+            xp: 0,
+            wallet: Wallet::default(),
+        },
+        land: vec![tile(long_ago), tile(now - Duration::from_secs(60 * 60))],
+        inventory: vec![],
+        gotchis: vec![],
+    };
+
+    let catch_up = hs.catch_up(now, &mut StdRng::seed_from_u64(3)).unwrap();
+    assert_eq!(
+        catch_up.cycles,
+        (MAX_CATCH_UP.as_millis() / FARM_CYCLE_MILLIS as u128) as u64
+    );
+    assert_eq!(catch_up.skipped, MAX_CATCH_UP);
+    assert!(hs.profile.last_farm <= now);
+    assert!(
+        now.duration_since(hs.profile.last_farm).unwrap()
+            < Duration::from_millis(FARM_CYCLE_MILLIS)
+    );
+    assert!(catch_up.events.windows(2).all(|w| w[0].at <= w[1].at));
+
+    // what's skipped over still pays out, all at once
+    let old_tile = hs.land[0].id;
+    assert!(catch_up.events.iter().any(|e| e.tile_id == old_tile
+        && e.at == now - MAX_CATCH_UP
+        && match &e.event {
+            PlantEvent::Yielded { items, .. } => !items.is_empty(),
+            _ => false,
+        }));
+
+    let late_tile = hs.land[1].id;
+    assert!(catch_up
+        .events
+        .iter()
+        .filter(|e| e.tile_id == late_tile)
+        .all(|e| e.at >= now - Duration::from_secs(60 * 60)));
+
+    // nothing more to catch up on
+    assert!(hs
+        .catch_up(now, &mut StdRng::seed_from_u64(3))
+        .unwrap()
+        .events
+        .is_empty());
+}
+
+#[test]
+fn applied_items_wear_off() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut tile = Tile {
+        acquired: SystemTime::now(),
+        plant: None,
+        id: uuid::Uuid::new_v4(),
+        steader: "bob".to_string(),
+        granted: false,
+    };
+    let powder = Possession::new(
+        CONFIG.find_possession_handle(&"Warp Powder").unwrap(),
+        possess::Owner::farmer("bob".to_string()),
+    );
+    assert!(match apply_item("bob", &powder, &mut tile) {
+        Err(ItemApplyError::NoPlant) => true,
+        _ => false,
+    });
+
+    tile.plant = Some(Plant::new(
+        CONFIG.find_plant_handle(&"Bractus").unwrap(),
+        vec![],
+    ));
+    // only on your own plants, with your own items
+    let alices = Possession::new(
+        powder.archetype_handle,
+        possess::Owner::farmer("alice".to_string()),
+    );
+    for (steader, item) in &[("alice", &powder), ("alice", &alices), ("bob", &alices)] {
+        assert!(match apply_item(steader, item, &mut tile) {
+            Err(ItemApplyError::NotOwned) => true,
+            _ => false,
+        });
+    }
+    assert!(tile.plant.as_ref().unwrap().effects.is_empty());
+
+    let effects = apply_item("bob", &powder, &mut tile).unwrap();
+    let duration = effects[0].duration.expect("Warp Powder doesn't wear off");
+
+    let plant = tile.plant.as_mut().unwrap();
+    let ctx = PlantContext::default();
+    let boosted = plant.advancements_sum(&ctx).total_extra_time_ticks;
+    assert!(boosted > 0);
+
+    let events = plant.grow(duration.ceil() as u64, &ctx, &mut StdRng::seed_from_u64(5));
+    assert!(events.iter().any(|e| match e {
+        PlantEvent::EffectExpired(_) => true,
+        _ => false,
+    }));
+    assert!(plant.effects.is_empty());
+    assert!(plant.advancements_sum(&ctx).total_extra_time_ticks < boosted);
+}
+
+#[cfg(test)]
+pub(crate) fn test_hacksteader(land: Vec<Tile>) -> Hacksteader {
+    Hacksteader {
+        user_id: "bob".to_string(),
+        profile: Profile {
+            joined: SystemTime::now(),
+            last_active: SystemTime::now(),
+            last_farm: SystemTime::now(),
+            id: "bob".to_string(),
+            xp: 0,
+            wallet: Wallet::default(),
+        },
+        land,
+        inventory: vec![],
+        gotchis: vec![],
+    }
+}
+
+#[cfg(test)]
+fn test_tile(plant: Option<&str>) -> Tile {
+    Tile {
+        acquired: SystemTime::now(),
+        plant: plant.map(|name| Plant::new(CONFIG.find_plant_handle(&name).unwrap(), vec![])),
+        id: uuid::Uuid::new_v4(),
+        steader: "bob".to_string(),
+        granted: false,
+    }
+}
+
+#[test]
+fn neighbors_of_the_same_archetype() {
+    let now = SystemTime::now();
+    let buddies = CONFIG
+        .find_plant(&"Bractus")
+        .unwrap()
+        .advancements
+        .all()
+        .position(|a| a.title == "Bread Buddies")
+        .unwrap();
+    let bractus = |buddy| {
+        let mut tile = test_tile(Some("Bractus"));
+        if buddy {
+            let plant = tile.plant.as_mut().unwrap();
+            plant.xp = plant.advancements.required_xp(buddies).unwrap();
+        }
+        tile
+    };
+    // "Bread Buddies" makes neighboring yields 15% bigger
+    let mut hs = test_hacksteader(vec![bractus(true), bractus(true), bractus(false)]);
+    let alone = |tile: &Tile| {
+        tile.plant
+            .as_ref()
+            .unwrap()
+            .advancements_sum(&PlantContext::default())
+            .yield_size_multiplier
+    };
+
+    let neighbors = hs.neighbor_advancements();
+    assert_eq!(neighbors[0].len(), 1);
+    assert_eq!(neighbors[1].len(), 1);
+    assert_eq!(neighbors[2].len(), 2);
+
+    let sums = hs.plant_sums(now).unwrap();
+    let boost = |i: usize| sums[i].as_ref().unwrap().yield_size_multiplier / alone(&hs.land[i]);
+    assert!((boost(0) - 1.15).abs() < 0.001);
+    assert!((boost(1) - 1.15).abs() < 0.001);
+    assert!((boost(2) - 1.15 * 1.15).abs() < 0.001);
+
+    // only other Bracti are helped
+    hs.land.push(test_tile(None));
+    hs.land.push(test_tile(Some("Coffea Cyl")));
+    assert!(hs.plant_sums(now).unwrap()[3].is_none());
+    let neighbors = hs.neighbor_advancements();
+    assert!(neighbors[3].is_empty());
+    assert!(neighbors[4].is_empty());
+    assert_eq!(neighbors[2].len(), 2);
+}
+
+#[test]
+fn owned_items_help_plants() {
+    let now = SystemTime::now();
+    let owned = |name| {
+        Possession::new(
+            CONFIG.find_possession_handle(&name).unwrap(),
+            possess::Owner::farmer("bob".to_string()),
+        )
+    };
+    let mut hs = test_hacksteader(vec![
+        test_tile(Some("Bractus")),
+        test_tile(Some("Coffea Cyl")),
+    ]);
+    hs.inventory.push(owned("Rolling Pin"));
+    hs.gotchis
+        .push(Possessed::from_possession(owned("Chefpheus")).unwrap());
+
+    let bractus = CONFIG.find_plant_handle(&"Bractus").unwrap();
+    let coffea = CONFIG.find_plant_handle(&"Coffea Cyl").unwrap();
+    assert_eq!(hs.item_advancements(bractus, now).unwrap().len(), 2);
+    assert!(hs.item_advancements(coffea, now).unwrap().is_empty());
+
+    // a gotchi in the inventory as well as among the gotchis doesn't help twice
+    let duplicate = hs.gotchis[0].clone().into_possession();
+    hs.inventory.push(duplicate);
+    assert_eq!(hs.item_advancements(bractus, now).unwrap().len(), 2);
+    hs.inventory.pop();
+
+    // nor do more of the same kind of item, or items up for sale
+    let mut another_pin = owned("Rolling Pin");
+    hs.inventory.push(another_pin.clone());
+    assert_eq!(hs.item_advancements(bractus, now).unwrap().len(), 2);
+    hs.inventory.retain(|p| p.name != "Rolling Pin");
+    another_pin.sale = Some(market::Sale {
+        price: 10,
+        market_name: "General".to_string(),
+    });
+    hs.inventory.push(another_pin);
+    assert_eq!(hs.item_advancements(bractus, now).unwrap().len(), 1);
+    hs.inventory.pop();
+    hs.inventory.push(owned("Rolling Pin"));
+
+    let contexts = hs.plant_contexts(now).unwrap();
+    assert_eq!(contexts[0].extra_advancements.len(), 2);
+    assert!(contexts[1].extra_advancements.is_empty());
+
+    // happier gotchis help more
+    let yield_size = |hs: &Hacksteader| {
+        hs.plant_sums(now).unwrap()[0]
+            .as_ref()
+            .unwrap()
+            .yield_size_multiplier
+    };
+    let before = yield_size(&hs);
+    let boost = hs.gotchis[0].inner.base_happiness;
+    hs.gotchis[0].boost_happiness(boost, now);
+    let happier = yield_size(&hs);
+    assert!(happier > before);
+
+    // and of several gotchis of the same kind, the happiest one counts
+    hs.gotchis
+        .push(Possessed::from_possession(owned("Chefpheus")).unwrap());
+    assert_eq!(yield_size(&hs), happier);
+}
+
+#[test]
+fn planted_seeds_keep_their_pedigree() {
+    let seed = || {
+        let mut seed: Possessed<possess::Seed> = Possessed::from_possession(Possession::new(
+            CONFIG.find_possession_handle(&"Bractus Seed").unwrap(),
+            possess::Owner::farmer("bob".to_string()),
+        ))
+        .unwrap();
+        seed.inner
+            .pedigree
+            .push(possess::seed::SeedGrower::new("alice".to_string(), 2));
+        seed
+    };
+    let mut tile = test_tile(None);
+
+    let pedigree = seed().inner.pedigree;
+    let plant = plant_seed(seed(), &mut tile).unwrap();
+    assert_eq!(plant.name, "Bractus");
+    assert_eq!(plant.pedigree, pedigree);
+
+    assert!(match plant_seed(seed(), &mut tile) {
+        Err(PlantSeedError::Occupied) => true,
+        _ => false,
+    });
+}
@@ -0,0 +1,932 @@
+use crate::config::{
+    spawn, ArchetypeHandle, CountProbability, EffectStacking, PlantAdvancement,
+    PlantAdvancementKind, PlantAdvancementSum, PlantArchetype, RecipeMakes,
+};
+use crate::{possess, Possession, CONFIG, FARM_CYCLE_MILLIS};
+use possess::{seed::SeedGrower, Owner, Seed};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plant {
+    pub xp: u64,
+    pub until_yield: f32,
+    pub craft: Option<Craft>,
+    pub pedigree: Vec<SeedGrower>,
+    pub archetype_handle: ArchetypeHandle,
+    /// Effects from items which have been applied to this plant.
+    pub effects: Vec<Effect>,
+}
+
+/// One of the effects of an item applied to a plant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Effect {
+    /// The id of the possession which was applied to the plant.
+    pub source: uuid::Uuid,
+    /// How many more farm cycles this effect lasts, if it ever wears off.
+    pub until_finish: Option<f32>,
+    pub item_archetype_handle: ArchetypeHandle,
+    /// Indexes into the effects of the item's ItemApplication.
+    pub effect_archetype_handle: ArchetypeHandle,
+    /// Stored alongside the effect so that it keeps working
+    /// the same way even if the item's config changes.
+    pub advancement: PlantAdvancement,
+}
+impl Effect {
+    /// Whether both of these came from the same effect of the same kind of item.
+    pub fn same_kind(&self, other: &Effect) -> bool {
+        self.item_archetype_handle == other.item_archetype_handle
+            && self.effect_archetype_handle == other.effect_archetype_handle
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Craft {
+    pub until_finish: f32,
+    pub total_cycles: f32,
+    pub destroys_plant: bool,
+    pub makes: RecipeMakes<ArchetypeHandle>,
+    /// Bounds for the xp awarded to the plant once the craft finishes.
+    pub xp: (u64, u64),
+    /// Possessions taken out of the inventory for this craft, to be used up once it finishes.
+    pub reserved: Vec<Possession>,
+}
+impl Craft {
+    fn finish(self, sum: &PlantAdvancementSum, rng: &mut impl Rng) -> CraftOutcome {
+        let mut makes = self.makes.clone().output(rng);
+        if rng.gen_range(0.0, 1.0) < sum.double_craft_yield_chance {
+            makes.append(&mut self.makes.clone().output(rng));
+        }
+
+        let (returned, used): (Vec<Possession>, Vec<Possession>) = self
+            .reserved
+            .into_iter()
+            .partition(|_| rng.gen_range(0.0, 1.0) < sum.craft_return_chance);
+
+        let (lo, hi) = self.xp;
+        CraftOutcome {
+            used: used.into_iter().map(|p| p.id).collect(),
+            returned,
+            makes,
+            xp: if hi > lo { rng.gen_range(lo, hi) } else { lo },
+            destroyed_plant: self.destroys_plant,
+        }
+    }
+}
+
+/// What a finished craft used up and produced.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CraftOutcome {
+    /// The ids of the reserved possessions the craft used up, which should be deleted.
+    pub used: Vec<uuid::Uuid>,
+    /// Reserved possessions given back thanks to the plant's craft return chance,
+    /// which should go back into the inventory.
+    pub returned: Vec<Possession>,
+    /// The archetypes of the possessions the craft made.
+    pub makes: Vec<ArchetypeHandle>,
+    pub xp: u64,
+    /// Whether the craft used up the plant, which should then be removed from its tile.
+    pub destroyed_plant: bool,
+}
+impl CraftOutcome {
+    /// The possessions this craft made, owned by the given steader.
+    pub fn possessions(&self, steader: &str) -> Vec<Possession> {
+        self.makes
+            .iter()
+            .map(|&ah| Possession::new(ah, Owner::crafter(steader.to_string())))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CraftError {
+    AlreadyCrafting,
+    /// Contains: the index of the recipe that doesn't exist
+    UnknownRecipe(usize),
+    MissingIngredients {
+        archetype_handle: ArchetypeHandle,
+        needs: usize,
+        has: usize,
+    },
+}
+impl fmt::Display for CraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CraftError::*;
+        match self {
+            AlreadyCrafting => write!(f, "this plant is already crafting something"),
+            UnknownRecipe(index) => write!(f, "this plant doesn't know a recipe #{}", index),
+            MissingIngredients {
+                archetype_handle,
+                needs,
+                has,
+            } => write!(
+                f,
+                "recipe needs {} {}, but only {} are available",
+                needs,
+                CONFIG
+                    .possession_archetypes
+                    .get(*archetype_handle)
+                    .map(|a| a.name.as_str())
+                    .unwrap_or("unknown items"),
+                has
+            ),
+        }
+    }
+}
+
+impl std::ops::Deref for Plant {
+    type Target = PlantArchetype;
+
+    fn deref(&self) -> &Self::Target {
+        &CONFIG
+            .plant_archetypes
+            .get(self.archetype_handle)
+            .expect("invalid archetype handle")
+    }
+}
+
+/// Everything outside of the plant itself which affects how it grows.
+#[derive(Debug, Clone)]
+pub struct PlantContext<'a> {
+    /// Advancements the plant benefits from on top of those it has unlocked itself,
+    /// i.e. those from neighboring plants, gotchis and keepsakes.
+    pub extra_advancements: Vec<Cow<'a, PlantAdvancement>>,
+    /// How long a single farm cycle lasts.
+    pub farm_cycle: Duration,
+    /// Whoever is growing the plant, so that they can be added to the pedigree of its seeds.
+    pub grower: Option<String>,
+}
+impl Default for PlantContext<'_> {
+    fn default() -> Self {
+        Self {
+            extra_advancements: vec![],
+            farm_cycle: Duration::from_millis(FARM_CYCLE_MILLIS),
+            grower: None,
+        }
+    }
+}
+
+/// The advancement as it applies to a plant grown from this many generations of seeds,
+/// or None if it doesn't apply at all.
+pub fn for_generations(
+    advancement: Cow<PlantAdvancement>,
+    generations: u64,
+) -> Option<Cow<PlantAdvancement>> {
+    let kind = advancement.kind.for_generations(generations)?;
+    if *kind == advancement.kind {
+        return Some(advancement);
+    }
+    let kind = kind.clone();
+    Some(Cow::Owned(PlantAdvancement {
+        kind,
+        ..advancement.into_owned()
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PlantEvent {
+    /// The plant's yields were rolled, producing these items and this much xp.
+    Yielded {
+        items: Vec<ArchetypeHandle>,
+        xp: u64,
+    },
+    /// The plant gained enough xp to unlock a new advancement.
+    AdvancementUnlocked(PlantAdvancement),
+    /// The plant produced seeds of its own kind when it yielded.
+    SeedsYielded(Vec<Seed>),
+    /// An effect from an item applied to this plant wore off.
+    EffectExpired(Effect),
+    /// The plant finished crafting something.
+    CraftFinished(CraftOutcome),
+}
+
+/// What happened while a plant was ticked.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tick {
+    /// How many whole farm cycles the plant grew for.
+    pub cycles: u64,
+    /// Time which didn't add up to a whole farm cycle,
+    /// and should be carried over to the next tick.
+    pub leftover: Duration,
+    pub events: Vec<PlantEvent>,
+}
+
+impl Plant {
+    pub fn new(archetype_handle: ArchetypeHandle, pedigree: Vec<SeedGrower>) -> Self {
+        Self {
+            xp: 0,
+            until_yield: CONFIG
+                .plant_archetypes
+                .get(archetype_handle)
+                .and_then(|a| a.base_yield_duration)
+                .unwrap_or(0.0),
+            craft: None,
+            effects: vec![],
+            pedigree,
+            archetype_handle,
+        }
+    }
+
+    /// All of the bonuses this plant currently has, in one place.
+    /// `Pedigree` advancements only count if the plant comes from enough generations of seeds.
+    pub fn advancements_sum(&self, ctx: &PlantContext) -> PlantAdvancementSum {
+        let generations = self.generations();
+        let extra: Vec<Cow<PlantAdvancement>> = self
+            .advancements
+            .unlocked(self.xp)
+            // the rest of the plant's own advancements are already summed up
+            .filter(|a| match a.kind {
+                PlantAdvancementKind::Pedigree(..) => true,
+                _ => false,
+            })
+            .chain(ctx.extra_advancements.iter().map(|a| a.as_ref()))
+            .chain(self.effects.iter().map(|e| &e.advancement))
+            .filter_map(|a| for_generations(Cow::Borrowed(a), generations))
+            .collect();
+
+        self.advancements
+            .sum(self.xp, extra.iter().map(|a| a.as_ref()))
+    }
+
+    /// Adds an effect to this plant, according to the stacking rules
+    /// for when the plant already has an effect of the same kind.
+    pub fn add_effect(&mut self, effect: Effect, stacking: EffectStacking) {
+        let existing = self.effects.iter().position(|e| e.same_kind(&effect));
+
+        match (stacking, existing) {
+            (EffectStacking::Refresh, Some(i)) => {
+                self.effects[i].until_finish = effect.until_finish;
+            }
+            (EffectStacking::Replace, Some(_)) => {
+                self.effects.retain(|e| !e.same_kind(&effect));
+                self.effects.push(effect);
+            }
+            _ => self.effects.push(effect),
+        }
+    }
+
+    /// Begins crafting the recipe at `recipe_index` in this plant's advancements sum,
+    /// taking the possessions it needs out of the inventory so that they can't be sold,
+    /// traded, fed or used in another craft while this one is underway.
+    /// Possessions which are up for sale are never used.
+    pub fn start_craft(
+        &mut self,
+        recipe_index: usize,
+        inventory: &mut Vec<Possession>,
+        ctx: &PlantContext,
+    ) -> Result<&Craft, CraftError> {
+        if self.craft.is_some() {
+            return Err(CraftError::AlreadyCrafting);
+        }
+
+        let recipe = self
+            .advancements_sum(ctx)
+            .recipes
+            .into_iter()
+            .nth(recipe_index)
+            .ok_or(CraftError::UnknownRecipe(recipe_index))?;
+
+        let mut reserved: Vec<uuid::Uuid> = vec![];
+        for &(needs, archetype_handle) in recipe.needs.iter() {
+            let available: Vec<uuid::Uuid> = inventory
+                .iter()
+                .filter(|p| p.archetype_handle == archetype_handle && p.sale.is_none())
+                .filter(|p| !reserved.contains(&p.id))
+                .map(|p| p.id)
+                .take(needs)
+                .collect();
+
+            if available.len() < needs {
+                return Err(CraftError::MissingIngredients {
+                    archetype_handle,
+                    needs,
+                    has: available.len(),
+                });
+            }
+            reserved.extend(available);
+        }
+
+        let (reserved, kept) = inventory.drain(..).partition(|p| reserved.contains(&p.id));
+        *inventory = kept;
+
+        Ok(self.craft.get_or_insert(Craft {
+            until_finish: recipe.time,
+            total_cycles: recipe.time,
+            destroys_plant: recipe.destroys_plant,
+            makes: recipe.makes,
+            xp: recipe.xp,
+            reserved,
+        }))
+    }
+
+    /// Stops crafting, returning the possessions which were set aside for the craft
+    /// so that they can be put back into the inventory.
+    pub fn cancel_craft(&mut self) -> Option<Vec<Possession>> {
+        self.craft.take().map(|c| c.reserved)
+    }
+
+    /// How many generations of seeds went into this plant altogether.
+    pub fn generations(&self) -> u64 {
+        SeedGrower::total_generations(&self.pedigree)
+    }
+
+    fn yield_seeds(
+        &self,
+        sum: &PlantAdvancementSum,
+        ctx: &PlantContext,
+        rng: &mut impl rand::Rng,
+    ) -> Vec<Seed> {
+        let count: usize = sum.seed_yields.iter().map(|cp| cp.gen_count(rng)).sum();
+        self.seeds(count, ctx)
+    }
+
+    fn seeds(&self, count: usize, ctx: &PlantContext) -> Vec<Seed> {
+        let archetype_handle = match CONFIG.find_seed_handle(&self.name) {
+            Ok(ah) if count > 0 => ah,
+            _ => return vec![],
+        };
+        let pedigree = match &ctx.grower {
+            Some(grower) => SeedGrower::grow_pedigree(&self.pedigree, grower),
+            None => self.pedigree.clone(),
+        };
+
+        (0..count)
+            .map(|_| Seed {
+                archetype_handle,
+                pedigree: pedigree.clone(),
+            })
+            .collect()
+    }
+
+    /// Grows the plant for as many whole farm cycles as fit into `elapsed`.
+    pub fn tick(
+        &mut self,
+        elapsed: Duration,
+        ctx: &PlantContext,
+        rng: &mut impl rand::Rng,
+    ) -> Tick {
+        let farm_cycle = ctx.farm_cycle.as_millis().max(1);
+        let cycles = (elapsed.as_millis() / farm_cycle) as u64;
+
+        Tick {
+            cycles,
+            leftover: Duration::from_millis((elapsed.as_millis() % farm_cycle) as u64),
+            events: self.grow(cycles, ctx, rng),
+        }
+    }
+
+    /// Grows the plant for a number of farm cycles. Rather than going cycle by cycle,
+    /// this skips straight from one yield or advancement to the next,
+    /// so even very long stretches of time are cheap to compute.
+    pub fn grow(
+        &mut self,
+        cycles: u64,
+        ctx: &PlantContext,
+        rng: &mut impl rand::Rng,
+    ) -> Vec<PlantEvent> {
+        self.grow_timed(cycles, ctx, rng)
+            .into_iter()
+            .map(|(_, e)| e)
+            .collect()
+    }
+
+    /// Like `grow`, but each event comes with the number of
+    /// farm cycles into the growth that it happened at.
+    pub fn grow_timed(
+        &mut self,
+        cycles: u64,
+        ctx: &PlantContext,
+        rng: &mut impl rand::Rng,
+    ) -> Vec<(u64, PlantEvent)> {
+        let mut events = vec![];
+        let mut sum = self.advancements_sum(ctx);
+        let mut remaining = cycles;
+
+        while remaining > 0 {
+            let ticks = 1 + sum.total_extra_time_ticks as u64;
+            let xp_per_cycle = sum.xp_multiplier * ticks;
+            let yield_per_cycle = sum.yield_speed_multiplier * ticks as f32;
+            let craft_per_cycle = sum.crafting_speed_multiplier * ticks as f32;
+            let position = self.advancements.current_position(self.xp);
+
+            let until_advancement = match self.advancements.required_xp(position + 1) {
+                Some(needed) if xp_per_cycle > 0 => {
+                    let missing = needed.saturating_sub(self.xp);
+                    ((missing + xp_per_cycle - 1) / xp_per_cycle).max(1)
+                }
+                _ => remaining,
+            };
+            let until_yield = match self.base_yield_duration {
+                Some(_) if yield_per_cycle > 0.0 => {
+                    (self.until_yield / yield_per_cycle).ceil().max(1.0) as u64
+                }
+                _ => remaining,
+            };
+
+            let until_craft = match &self.craft {
+                Some(craft) if craft_per_cycle > 0.0 => {
+                    (craft.until_finish / craft_per_cycle).ceil().max(1.0) as u64
+                }
+                _ => remaining,
+            };
+
+            let until_effect_expires = self
+                .effects
+                .iter()
+                .filter_map(|e| e.until_finish)
+                .map(|until_finish| until_finish.ceil().max(1.0) as u64)
+                .min()
+                .unwrap_or(remaining);
+
+            let step = remaining
+                .min(until_advancement)
+                .min(until_yield)
+                .min(until_craft)
+                .min(until_effect_expires);
+            remaining -= step;
+            let at = cycles - remaining;
+            self.xp += xp_per_cycle * step;
+
+            if let Some(base_yield_duration) = self.base_yield_duration {
+                self.until_yield -= yield_per_cycle * step as f32;
+                while self.until_yield <= 0.0 {
+                    self.until_yield += base_yield_duration.max(1.0);
+
+                    if !sum.yields.is_empty() {
+                        let (items, xp): (Vec<ArchetypeHandle>, Vec<usize>) =
+                            spawn(&sum.yields, rng).unzip();
+                        let xp = xp.into_iter().sum::<usize>() as u64;
+
+                        self.xp += xp;
+                        events.push((at, PlantEvent::Yielded { items, xp }));
+                    }
+
+                    let seeds = self.yield_seeds(&sum, ctx, rng);
+                    if !seeds.is_empty() {
+                        events.push((at, PlantEvent::SeedsYielded(seeds)));
+                    }
+                }
+            }
+
+            let craft_finished = match &mut self.craft {
+                Some(craft) => {
+                    craft.until_finish -= craft_per_cycle * step as f32;
+                    craft.until_finish <= 0.0
+                }
+                None => false,
+            };
+            if let Some(craft) = self.craft.take().filter(|_| craft_finished) {
+                let outcome = craft.finish(&sum, rng);
+                let destroyed_plant = outcome.destroyed_plant;
+
+                self.xp += outcome.xp;
+                events.push((at, PlantEvent::CraftFinished(outcome)));
+
+                // there's no plant left to grow
+                if destroyed_plant {
+                    return events;
+                }
+            }
+
+            let mut effects_expired = false;
+            for effect in self.effects.iter_mut() {
+                if let Some(until_finish) = &mut effect.until_finish {
+                    *until_finish -= step as f32;
+                }
+            }
+            while let Some(i) = self
+                .effects
+                .iter()
+                .position(|e| e.until_finish.map(|u| u <= 0.0).unwrap_or(false))
+            {
+                effects_expired = true;
+                events.push((at, PlantEvent::EffectExpired(self.effects.remove(i))));
+            }
+
+            let new_position = self.advancements.current_position(self.xp);
+            if effects_expired {
+                sum = self.advancements_sum(ctx);
+            }
+            if new_position != position {
+                for unlocked in position + 1..=new_position {
+                    if let Some(adv) = self.advancements.get(unlocked) {
+                        events.push((at, PlantEvent::AdvancementUnlocked(adv.clone())));
+                    }
+                }
+                sum = self.advancements_sum(ctx);
+            }
+        }
+
+        events
+    }
+
+    /// Grows the plant for a number of farm cycles in closed form. Instead of rolling each
+    /// yield, the plant gets the whole items and xp its yields are expected to give, all in one
+    /// event, so this takes just as long no matter how many cycles are skipped. Advancements
+    /// are unlocked along the way, but effects only wear off at the end.
+    pub fn skip(
+        &mut self,
+        cycles: u64,
+        ctx: &PlantContext,
+        rng: &mut impl rand::Rng,
+    ) -> Vec<PlantEvent> {
+        let mut events = vec![];
+        let mut expected_items: Vec<(ArchetypeHandle, f64)> = vec![];
+        let mut expected_seeds = 0.0;
+        let mut yield_xp = 0;
+        let mut remaining = cycles;
+
+        // each stretch between advancements has the same bonuses throughout
+        while remaining > 0 {
+            let sum = self.advancements_sum(ctx);
+            let ticks = 1 + sum.total_extra_time_ticks as u64;
+            let xp_per_cycle = sum.xp_multiplier * ticks;
+            let position = self.advancements.current_position(self.xp);
+
+            let step = match self.advancements.required_xp(position + 1) {
+                Some(needed) if xp_per_cycle > 0 => {
+                    let missing = needed.saturating_sub(self.xp);
+                    ((missing + xp_per_cycle - 1) / xp_per_cycle).max(1)
+                }
+                _ => remaining,
+            }
+            .min(remaining);
+            remaining -= step;
+            self.xp = self.xp.saturating_add(xp_per_cycle.saturating_mul(step));
+
+            if let Some(base_yield_duration) = self.base_yield_duration {
+                let base_yield_duration = base_yield_duration.max(1.0) as f64;
+                let progress = (sum.yield_speed_multiplier * ticks as f32) as f64 * step as f64;
+                let until_yield = self.until_yield as f64;
+
+                // just like in grow_timed, a yield happens once until_yield reaches zero
+                let yields = if progress >= until_yield {
+                    1 + ((progress - until_yield) / base_yield_duration).floor() as u64
+                } else {
+                    0
+                };
+                self.until_yield =
+                    (until_yield + yields as f64 * base_yield_duration - progress) as f32;
+
+                for y in sum.yields.iter() {
+                    let expected = CountProbability(y.chance, y.amount).expected_count() as f64;
+                    match expected_items.iter_mut().find(|(ah, _)| *ah == y.yields) {
+                        Some((_, count)) => *count += expected * yields as f64,
+                        None => expected_items.push((y.yields, expected * yields as f64)),
+                    }
+                }
+                let xp = (sum
+                    .yields
+                    .iter()
+                    .map(|y| crate::simulate::expected_yield_xp(y) as f64)
+                    .sum::<f64>()
+                    * yields as f64) as u64;
+                self.xp = self.xp.saturating_add(xp);
+                yield_xp += xp;
+
+                expected_seeds += sum
+                    .seed_yields
+                    .iter()
+                    .map(|cp| cp.expected_count() as f64)
+                    .sum::<f64>()
+                    * yields as f64;
+            }
+
+            let craft_per_cycle = sum.crafting_speed_multiplier * ticks as f32;
+            let craft_finished = match &mut self.craft {
+                Some(craft) => {
+                    craft.until_finish -= craft_per_cycle * step as f32;
+                    craft.until_finish <= 0.0
+                }
+                None => false,
+            };
+            if let Some(craft) = self.craft.take().filter(|_| craft_finished) {
+                let outcome = craft.finish(&sum, rng);
+                let destroyed_plant = outcome.destroyed_plant;
+
+                self.xp = self.xp.saturating_add(outcome.xp);
+                events.push(PlantEvent::CraftFinished(outcome));
+
+                // there's no plant left to grow
+                if destroyed_plant {
+                    return events;
+                }
+            }
+
+            let new_position = self.advancements.current_position(self.xp);
+            for unlocked in position + 1..=new_position {
+                if let Some(adv) = self.advancements.get(unlocked) {
+                    events.push(PlantEvent::AdvancementUnlocked(adv.clone()));
+                }
+            }
+        }
+
+        let items: Vec<ArchetypeHandle> = expected_items
+            .into_iter()
+            .flat_map(|(ah, count)| std::iter::repeat(ah).take(count as usize))
+            .collect();
+        if !items.is_empty() || yield_xp > 0 {
+            events.push(PlantEvent::Yielded {
+                items,
+                xp: yield_xp,
+            });
+        }
+        let seeds = self.seeds(expected_seeds as usize, ctx);
+        if !seeds.is_empty() {
+            events.push(PlantEvent::SeedsYielded(seeds));
+        }
+
+        for effect in self.effects.iter_mut() {
+            if let Some(until_finish) = &mut effect.until_finish {
+                *until_finish -= cycles as f32;
+            }
+        }
+        while let Some(i) = self
+            .effects
+            .iter()
+            .position(|e| e.until_finish.map(|u| u <= 0.0).unwrap_or(false))
+        {
+            events.push(PlantEvent::EffectExpired(self.effects.remove(i)));
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+fn test_plant(name: &str) -> Plant {
+    Plant::new(
+        CONFIG
+            .find_plant_handle(&name)
+            .unwrap_or_else(|e| panic!("{}", e)),
+        vec![SeedGrower::new("bob".to_string(), 0)],
+    )
+}
+
+#[test]
+fn plant_tick_unlocks_and_yields() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut plant = test_plant("Bractus");
+    let tick = plant.tick(
+        Duration::from_secs(60 * 60 * 24) + Duration::from_millis(500),
+        &PlantContext::default(),
+        &mut StdRng::seed_from_u64(0),
+    );
+
+    assert_eq!(tick.cycles, 60 * 60 * 24 * 1000 / FARM_CYCLE_MILLIS);
+    assert_eq!(tick.leftover, Duration::from_millis(500));
+    assert!(tick.events.iter().any(|e| match e {
+        PlantEvent::Yielded { items, .. } => !items.is_empty(),
+        _ => false,
+    }));
+
+    let unlocked: Vec<&PlantAdvancement> = tick
+        .events
+        .iter()
+        .filter_map(|e| match e {
+            PlantEvent::AdvancementUnlocked(adv) => Some(adv),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        unlocked.len(),
+        plant.advancements.current_position(plant.xp),
+        "every advancement the plant reached should've been reported"
+    );
+}
+
+#[test]
+fn plants_skip_years_all_at_once() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let ctx = PlantContext::default();
+    let mut plant = test_plant("Bractus");
+    let cycles = 60 * 60 * 24 * 365 * 10 * 1000 / FARM_CYCLE_MILLIS;
+
+    let events = plant.skip(cycles, &ctx, &mut StdRng::seed_from_u64(0));
+    let yielded = events
+        .iter()
+        .filter_map(|e| match e {
+            PlantEvent::Yielded { items, xp } => Some((items.len(), *xp)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(yielded.len(), 1, "every yield should be rolled into one");
+    assert!(yielded[0].0 > 0);
+    assert!(plant.xp > yielded[0].1);
+    assert!(plant.until_yield > 0.0 && plant.until_yield <= plant.base_yield_duration.unwrap());
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| match e {
+                PlantEvent::AdvancementUnlocked(_) => true,
+                _ => false,
+            })
+            .count(),
+        plant.advancements.current_position(plant.xp)
+    );
+
+    assert!(plant
+        .skip(0, &ctx, &mut StdRng::seed_from_u64(0))
+        .is_empty());
+}
+
+#[test]
+fn plant_tick_is_independent_of_tick_size() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let ctx = PlantContext::default();
+    let mut all_at_once = test_plant("Coffea Cyl");
+    let mut hourly = all_at_once.clone();
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let events = all_at_once
+        .tick(Duration::from_secs(60 * 60 * 12), &ctx, &mut rng)
+        .events;
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let hourly_events: Vec<PlantEvent> = (0..12)
+        .flat_map(|_| {
+            hourly
+                .tick(Duration::from_secs(60 * 60), &ctx, &mut rng)
+                .events
+        })
+        .collect();
+
+    assert_eq!(all_at_once.xp, hourly.xp);
+    assert_eq!(events, hourly_events);
+}
+
+#[test]
+fn plant_crafts() {
+    use crate::config::PlantAdvancementKind;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let ctx = PlantContext::default();
+    let mut plant = test_plant("Bractus");
+    let craft_advancement = plant
+        .advancements
+        .all()
+        .position(|a| match a.kind {
+            PlantAdvancementKind::Craft(_) => true,
+            _ => false,
+        })
+        .expect("Bractus can't craft");
+    plant.xp = plant.advancements.required_xp(craft_advancement).unwrap();
+    let recipe = plant.advancements_sum(&ctx).recipes[0].clone();
+
+    let mut inventory: Vec<Possession> = recipe
+        .needs
+        .iter()
+        .flat_map(|&(n, ah)| (0..n).map(move |_| Possession::new(ah, Owner::farmer("bob".into()))))
+        .collect();
+    let ingredients = inventory.len();
+
+    let mut missing_one = inventory[1..].to_vec();
+    assert_eq!(
+        plant.start_craft(0, &mut missing_one, &ctx).unwrap_err(),
+        CraftError::MissingIngredients {
+            archetype_handle: recipe.needs[0].1,
+            needs: recipe.needs[0].0,
+            has: recipe.needs[0].0 - 1,
+        }
+    );
+    assert_eq!(missing_one.len(), ingredients - 1);
+
+    // the ingredients leave the inventory, so another plant can't use them too
+    let mut other = plant.clone();
+    plant.start_craft(0, &mut inventory, &ctx).unwrap();
+    assert!(inventory.is_empty());
+    assert_eq!(
+        plant.start_craft(0, &mut inventory, &ctx).unwrap_err(),
+        CraftError::AlreadyCrafting
+    );
+    match other.start_craft(0, &mut inventory, &ctx).unwrap_err() {
+        CraftError::MissingIngredients { has: 0, .. } => {}
+        e => panic!("crafted without ingredients: {:?}", e),
+    }
+
+    let events = plant.grow(
+        recipe.time.ceil() as u64,
+        &ctx,
+        &mut StdRng::seed_from_u64(2),
+    );
+    let outcome = events
+        .into_iter()
+        .find_map(|e| match e {
+            PlantEvent::CraftFinished(outcome) => Some(outcome),
+            _ => None,
+        })
+        .expect("craft didn't finish");
+
+    assert!(plant.craft.is_none());
+    assert_eq!(outcome.used.len() + outcome.returned.len(), ingredients);
+    assert!(outcome
+        .possessions("bob")
+        .iter()
+        .all(|p| p.ownership_log == vec![Owner::crafter("bob".into())]));
+}
+
+#[test]
+fn effect_stacking() {
+    let mut plant = test_plant("Bractus");
+    let effect = |until_finish| Effect {
+        source: uuid::Uuid::new_v4(),
+        until_finish: Some(until_finish),
+        item_archetype_handle: 0,
+        effect_archetype_handle: 0,
+        advancement: plant.advancements.base.clone(),
+    };
+    let (first, second) = (effect(10.0), effect(20.0));
+
+    plant.add_effect(first.clone(), EffectStacking::Stack);
+    plant.add_effect(second.clone(), EffectStacking::Stack);
+    assert_eq!(plant.effects.len(), 2);
+
+    plant.effects = vec![first.clone()];
+    plant.effects[0].until_finish = Some(3.0);
+    plant.add_effect(second.clone(), EffectStacking::Refresh);
+    assert_eq!(plant.effects.len(), 1);
+    assert_eq!(plant.effects[0].source, first.source);
+    assert_eq!(plant.effects[0].until_finish, Some(20.0));
+
+    plant.add_effect(second.clone(), EffectStacking::Replace);
+    assert_eq!(plant.effects, vec![second]);
+}
+
+#[test]
+fn plants_yield_seeds() {
+    use crate::config::{CountProbability, PlantAdvancementKind};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let seed_yield = PlantAdvancement {
+        xp: 0,
+        title: "Seedy".to_string(),
+        description: String::new(),
+        achiever_title: String::new(),
+        art: String::new(),
+        kind: PlantAdvancementKind::SeedYield(CountProbability(1.0, (1.0, 1.5))),
+    };
+    let ctx = PlantContext {
+        extra_advancements: vec![Cow::Borrowed(&seed_yield)],
+        grower: Some("alice".to_string()),
+        ..Default::default()
+    };
+    let mut plant = test_plant("Bractus");
+    let cycles = plant.base_yield_duration.unwrap().ceil() as u64;
+
+    let seeds: Vec<Seed> = plant
+        .grow(cycles, &ctx, &mut StdRng::seed_from_u64(6))
+        .into_iter()
+        .flat_map(|e| match e {
+            PlantEvent::SeedsYielded(seeds) => seeds,
+            _ => vec![],
+        })
+        .collect();
+
+    assert!(!seeds.is_empty());
+    let bractus_seed = CONFIG.find_seed_handle(&"Bractus").unwrap();
+    for seed in seeds {
+        assert_eq!(seed.archetype_handle, bractus_seed);
+        assert_eq!(
+            seed.pedigree,
+            vec![
+                SeedGrower::new("bob".to_string(), 0),
+                SeedGrower::new("alice".to_string(), 1)
+            ]
+        );
+        let possession = seed.into_possession(Owner::farmer("alice".to_string()));
+        assert_eq!(possession.kind.seed().unwrap().pedigree.len(), 2);
+    }
+}
+
+#[test]
+fn pedigree_advancements_need_enough_generations() {
+    let pedigree_xp = PlantAdvancement {
+        xp: 0,
+        title: "Heirloom".to_string(),
+        description: String::new(),
+        achiever_title: String::new(),
+        art: String::new(),
+        kind: PlantAdvancementKind::Pedigree(2, Box::new(PlantAdvancementKind::Xp(5.0))),
+    };
+    let ctx = PlantContext {
+        extra_advancements: vec![Cow::Borrowed(&pedigree_xp)],
+        ..Default::default()
+    };
+    let mut plant = test_plant("Bractus");
+    let base = plant
+        .advancements_sum(&PlantContext::default())
+        .xp_multiplier;
+
+    assert_eq!(plant.advancements_sum(&ctx).xp_multiplier, base);
+    plant.pedigree = vec![
+        SeedGrower::new("bob".to_string(), 1),
+        SeedGrower::new("alice".to_string(), 1),
+    ];
+    assert_eq!(plant.advancements_sum(&ctx).xp_multiplier, base + 5);
+}
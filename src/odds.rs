@@ -0,0 +1,219 @@
+//! Exact odds for the things players can get out of loot tables, yields and recipes,
+//! so that frontends can show players their true chances instead of percentiles.
+use crate::config::{CountProbability, LootTable, RecipeMakes, Yield};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// The chances of getting each number of some item;
+/// the number at index 2 is the probability of getting exactly 2.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CountDistribution(pub Vec<f32>);
+impl CountDistribution {
+    /// Always exactly this many.
+    pub fn certain(count: usize) -> Self {
+        let mut odds = vec![0.0; count + 1];
+        odds[count] = 1.0;
+        CountDistribution(odds)
+    }
+
+    pub fn chance_of(&self, count: usize) -> f32 {
+        self.0.get(count).copied().unwrap_or(0.0)
+    }
+
+    /// The chance of getting at least one.
+    pub fn chance_of_any(&self) -> f32 {
+        1.0 - self.chance_of(0)
+    }
+
+    pub fn mean(&self) -> f32 {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(count, chance)| count as f32 * chance)
+            .sum()
+    }
+
+    pub fn variance(&self) -> f32 {
+        let mean = self.mean();
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(count, chance)| (count as f32 - mean).powi(2) * chance)
+            .sum()
+    }
+
+    /// The odds of the total count when both of these are rolled independently.
+    pub fn plus(&self, other: &Self) -> Self {
+        let mut odds = vec![0.0; self.0.len() + other.0.len() - 1];
+        for (a, a_chance) in self.0.iter().enumerate() {
+            for (b, b_chance) in other.0.iter().enumerate() {
+                odds[a + b] += a_chance * b_chance;
+            }
+        }
+        CountDistribution(odds)
+    }
+
+    /// The odds when one of several distributions is picked according to its weight.
+    pub fn mix(weighted: &[(f32, &Self)]) -> Self {
+        let len = weighted.iter().map(|(_, d)| d.0.len()).max().unwrap_or(1);
+        let mut odds = vec![0.0; len];
+        for (weight, dist) in weighted.iter() {
+            for (count, chance) in dist.0.iter().enumerate() {
+                odds[count] += weight * chance;
+            }
+        }
+        CountDistribution(odds)
+    }
+}
+impl From<CountProbability> for CountDistribution {
+    fn from(cp: CountProbability) -> Self {
+        CountDistribution(cp.count_distribution())
+    }
+}
+impl fmt::Display for CountDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .enumerate()
+                .filter(|(_, &chance)| chance > 0.0)
+                .map(|(count, chance)| format!("*{}* (*{:.2}%* chance)", count, chance * 100.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// The odds of getting each number of each item from a table of independently rolled rows,
+/// summing the counts of rows that produce the same thing.
+pub fn table_odds<Handle: Ord>(
+    rows: impl IntoIterator<Item = (CountProbability, Handle)>,
+) -> BTreeMap<Handle, CountDistribution> {
+    let mut odds: BTreeMap<Handle, CountDistribution> = BTreeMap::new();
+    for (cp, handle) in rows {
+        let row = CountDistribution::from(cp);
+        let total = match odds.remove(&handle) {
+            Some(existing) => existing.plus(&row),
+            None => row,
+        };
+        odds.insert(handle, total);
+    }
+    odds
+}
+
+/// The odds of what hatches out of an egg's hatch table.
+pub fn loot_table_odds(table: &LootTable) -> BTreeMap<String, CountDistribution> {
+    table_odds(table.iter().cloned())
+}
+
+/// The odds of what comes from a plant's yields whenever they are rolled.
+pub fn yield_odds<Handle: Ord + Clone>(
+    yields: &[Yield<Handle>],
+) -> BTreeMap<Handle, CountDistribution> {
+    table_odds(
+        yields
+            .iter()
+            .map(|y| (CountProbability(y.chance, y.amount), y.yields.clone())),
+    )
+}
+
+/// The odds of getting each number of each item when a recipe's output is rolled.
+pub fn recipe_odds<Handle: Ord + Clone>(
+    makes: &RecipeMakes<Handle>,
+) -> BTreeMap<Handle, CountDistribution> {
+    use RecipeMakes::*;
+
+    match makes {
+        Just(count, h) => [(h.clone(), CountDistribution::certain(*count))]
+            .iter()
+            .cloned()
+            .collect(),
+        AllOf(these) => {
+            let mut odds: BTreeMap<Handle, CountDistribution> = BTreeMap::new();
+            for (count, h) in these.iter() {
+                let total = match odds.remove(h) {
+                    Some(existing) => existing.plus(&CountDistribution::certain(*count)),
+                    None => CountDistribution::certain(*count),
+                };
+                odds.insert(h.clone(), total);
+            }
+            odds
+        }
+        OneOf(these) => {
+            let branches: Vec<(f32, BTreeMap<Handle, CountDistribution>)> = these
+                .iter()
+                .map(|(chance, makes)| (*chance, recipe_odds(makes)))
+                .collect();
+            let none = CountDistribution::certain(0);
+
+            // branches that don't produce a given item produce none of it
+            let handles: BTreeSet<Handle> = branches
+                .iter()
+                .flat_map(|(_, odds)| odds.keys().cloned())
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| {
+                    let weighted: Vec<(f32, &CountDistribution)> = branches
+                        .iter()
+                        .map(|(chance, odds)| (*chance, odds.get(&h).unwrap_or(&none)))
+                        .collect();
+                    let mixed = CountDistribution::mix(&weighted);
+                    (h, mixed)
+                })
+                .collect()
+        }
+        Nothing => BTreeMap::new(),
+    }
+}
+
+#[test]
+fn nested_recipe_odds() {
+    let makes = RecipeMakes::OneOf(vec![
+        (0.25, RecipeMakes::Just(4, "Bread Essence")),
+        (
+            0.75,
+            RecipeMakes::OneOf(vec![
+                (
+                    0.5,
+                    RecipeMakes::AllOf(vec![(2, "Bread Essence"), (1, "Cyl Crystal")]),
+                ),
+                (0.5, RecipeMakes::Nothing),
+            ]),
+        ),
+    ]);
+    let odds = recipe_odds(&makes);
+
+    let bread = &odds["Bread Essence"];
+    assert!((bread.chance_of(4) - 0.25).abs() < 0.0001);
+    assert!((bread.chance_of(2) - 0.375).abs() < 0.0001);
+    assert!((bread.chance_of(0) - 0.375).abs() < 0.0001);
+    assert!((bread.mean() - 1.75).abs() < 0.0001);
+    // E[x^2] = 0.25 * 16 + 0.375 * 4 = 5.5, so 5.5 - 1.75^2
+    assert!((bread.variance() - 2.4375).abs() < 0.0001);
+
+    let crystal = &odds["Cyl Crystal"];
+    assert!((crystal.chance_of_any() - 0.375).abs() < 0.0001);
+}
+
+#[test]
+fn table_rows_for_the_same_item_add_up() {
+    let table: LootTable = vec![
+        (CountProbability(0.5, (1.0, 1.0)), "Gempheus".to_string()),
+        (CountProbability(0.5, (1.0, 1.0)), "Gempheus".to_string()),
+        (CountProbability(1.0, (0.0, 2.0)), "Cyl Crystal".to_string()),
+    ];
+    let odds = loot_table_odds(&table);
+
+    let gempheus = &odds["Gempheus"];
+    assert_eq!(gempheus.0, vec![0.25, 0.5, 0.25]);
+    assert!((gempheus.mean() - 1.0).abs() < 0.0001);
+    assert!((gempheus.variance() - 0.5).abs() < 0.0001);
+
+    let crystal = &odds["Cyl Crystal"];
+    assert!((crystal.mean() - 1.0).abs() < 0.0001);
+    assert!((crystal.0.iter().sum::<f32>() - 1.0).abs() < 0.0001);
+}
@@ -1,8 +1,19 @@
-use super::{Possessable, PossessionKind};
-use crate::{config, AttributeParseError, Item, CONFIG};
-use config::{ArchetypeHandle, ArchetypeKind};
-use rusoto_dynamodb::AttributeValue;
+use super::{Owner, Possessable, Possessed, Possession, PossessionKind};
+use crate::currency::{LedgerEntry, LedgerReason};
+use crate::market::{s, values};
+use crate::{config, AttributeParseError, Item, Key, Profile, CONFIG, TABLE_NAME};
+use config::{ArchetypeHandle, ArchetypeKind, ConfigError, LootTable};
+use humantime::{format_rfc3339, parse_rfc3339};
+use rusoto_dynamodb::{AttributeValue, DynamoDb, DynamoDbClient};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Feeding a gotchi can make its plant effects at most this many times as strong.
+pub const MAX_PLANT_EFFECT_STRENGTH: f32 = 2.0;
+
+/// Gotchis earn as much gp as they are happy for each of these that passes.
+pub const HARVEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
 pub struct GotchiHarvestOwner {
@@ -61,11 +72,44 @@ impl Into<AttributeValue> for GotchiHarvestOwner {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Gotchi {
     archetype_handle: ArchetypeHandle,
     pub nickname: String,
     pub harvest_log: Vec<GotchiHarvestOwner>,
+    /// How happy this gotchi was as of `happiness_updated`.
+    pub happiness: u64,
+    pub happiness_updated: SystemTime,
+    /// Everything this gotchi earned before this has already been harvested.
+    pub last_harvest: SystemTime,
+}
+impl Default for Gotchi {
+    fn default() -> Self {
+        Self {
+            archetype_handle: 0,
+            nickname: String::new(),
+            harvest_log: vec![],
+            happiness: 0,
+            happiness_updated: SystemTime::UNIX_EPOCH,
+            last_harvest: SystemTime::UNIX_EPOCH,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedError {
+    /// Contains: the names of the gotchi and the item it won't eat
+    NotFood(String, String),
+    OnSale,
+}
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FeedError::*;
+        match self {
+            NotFood(gotchi, food) => write!(f, "{} won't eat {}", gotchi, food),
+            OnSale => write!(f, "items can't be fed to gotchis while they're up for sale"),
+        }
+    }
 }
 impl Possessable for Gotchi {
     fn from_possession_kind(pk: PossessionKind) -> Option<Self> {
@@ -96,6 +140,7 @@ impl std::ops::Deref for Gotchi {
 
 impl Gotchi {
     pub fn new(archetype_handle: ArchetypeHandle, owner_id: &str) -> Self {
+        let now = SystemTime::now();
         Self {
             archetype_handle,
             nickname: CONFIG.possession_archetypes[archetype_handle].name.clone(),
@@ -103,7 +148,84 @@ impl Gotchi {
                 id: owner_id.to_string(),
                 harvested: 0,
             }],
+            happiness: match &CONFIG.possession_archetypes[archetype_handle].kind {
+                ArchetypeKind::Gotchi(g) => g.base_happiness,
+                _ => 0,
+            },
+            happiness_updated: now,
+            last_harvest: now,
+        }
+    }
+
+    /// How happy this gotchi is at the given time. Happiness gained above the gotchi's
+    /// base happiness wears off with each harvest interval, but never drops below it.
+    pub fn happiness_at(&self, now: SystemTime) -> u64 {
+        let intervals = now
+            .duration_since(self.happiness_updated)
+            .unwrap_or_default()
+            .as_secs()
+            / HARVEST_INTERVAL.as_secs();
+        let decayed = self
+            .happiness
+            .saturating_sub(intervals.saturating_mul(self.happiness_decay));
+
+        decayed.max(self.base_happiness.min(self.happiness))
+    }
+
+    /// How strong this gotchi's plant effects are at the given time, relative to
+    /// how strong they'd be if the gotchi were only as happy as its base happiness.
+    pub fn plant_effect_strength(&self, now: SystemTime) -> f32 {
+        if self.base_happiness == 0 {
+            return 1.0;
         }
+
+        (self.happiness_at(now) as f32 / self.base_happiness as f32).min(MAX_PLANT_EFFECT_STRENGTH)
+    }
+
+    /// How many whole harvest intervals have passed since the last harvest,
+    /// and how much this gotchi earned over them. Each interval earns as much
+    /// as the gotchi was happy when it began.
+    pub fn unharvested(&self, now: SystemTime) -> (u32, u64) {
+        let interval = HARVEST_INTERVAL.as_secs();
+        let intervals = (now
+            .duration_since(self.last_harvest)
+            .unwrap_or_default()
+            .as_secs()
+            / interval) as u32;
+
+        // intervals which began before the gotchi's happiness was last updated earn all of it
+        let before = match self.happiness_updated.duration_since(self.last_harvest) {
+            Ok(gap) => {
+                let interval = HARVEST_INTERVAL.as_nanos();
+                ((gap.as_nanos() + interval - 1) / interval).min(intervals as u128) as u64
+            }
+            Err(_) => 0,
+        };
+        let after = intervals as u64 - before;
+
+        // after that, happiness wears off by the same amount each interval
+        // until it's down to the floor, where it stays
+        let decayed_already = (self.last_harvest + HARVEST_INTERVAL * before as u32)
+            .duration_since(self.happiness_updated)
+            .unwrap_or_default()
+            .as_secs()
+            / interval;
+        let floor = self.base_happiness.min(self.happiness) as u128;
+        let (happiness, decay) = (self.happiness as u128, self.happiness_decay as u128);
+        let decaying = match decay {
+            0 => after,
+            _ => {
+                let until_floor = (happiness - floor + decay - 1) / decay;
+                (until_floor.saturating_sub(decayed_already as u128) as u64).min(after)
+            }
+        } as u128;
+
+        let earned = before as u128 * happiness + decaying * happiness
+            - decay
+                * (decaying * decayed_already as u128 + decaying * decaying.saturating_sub(1) / 2)
+            + (after as u128 - decaying) * floor;
+
+        (intervals, earned.min(u64::MAX as u128) as u64)
     }
     pub fn fill_from_item(&mut self, item: &Item) -> Result<(), AttributeParseError> {
         use AttributeParseError::*;
@@ -134,6 +256,22 @@ impl Gotchi {
             })
             .collect();
 
+        // gotchis from before happiness was tracked are only as happy as they started out
+        if let Some(happiness) = item.get("happiness").and_then(|v| v.n.as_ref()) {
+            self.happiness = happiness
+                .parse()
+                .map_err(|e| IntFieldParse("happiness", e))?;
+        }
+        if let Some(updated) = item.get("happiness_updated").and_then(|v| v.s.as_ref()) {
+            self.happiness_updated =
+                parse_rfc3339(updated).map_err(|e| TimeFieldParse("happiness_updated", e))?;
+        }
+        // and those from before harvests were timed start earning from when they were last fed
+        self.last_harvest = match item.get("last_harvest").and_then(|v| v.s.as_ref()) {
+            Some(last) => parse_rfc3339(last).map_err(|e| TimeFieldParse("last_harvest", e))?,
+            None => self.happiness_updated,
+        };
+
         Ok(())
     }
     pub fn write_item(&self, item: &mut Item) {
@@ -157,8 +295,336 @@ impl Gotchi {
                 ..Default::default()
             },
         );
+        item.insert(
+            "happiness".to_string(),
+            AttributeValue {
+                n: Some(self.happiness.to_string()),
+                ..Default::default()
+            },
+        );
+        item.insert(
+            "happiness_updated".to_string(),
+            AttributeValue {
+                s: Some(format_rfc3339(self.happiness_updated).to_string()),
+                ..Default::default()
+            },
+        );
+        item.insert(
+            "last_harvest".to_string(),
+            AttributeValue {
+                s: Some(format_rfc3339(self.last_harvest).to_string()),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// What a gotchi earned for its owner since it was last harvested.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Harvest {
+    pub gotchi: uuid::Uuid,
+    /// Contains: the id of the steader who should be credited with this harvest
+    pub owner: String,
+    pub amount: u64,
+}
+/// Stores a harvest, when added to the SET clause of an update to a gotchi.
+pub(crate) const HARVEST_SET: &str = "last_harvest = :last_harvest, harvest_log = :harvest_log";
+/// Makes sure a gotchi wasn't harvested since it was loaded,
+/// when added to the condition of an update to it.
+pub(crate) const HARVEST_CONDITION: &str =
+    "(attribute_not_exists(last_harvest) OR last_harvest = :harvested_from)";
+
+impl Gotchi {
+    /// The values `HARVEST_SET` and `HARVEST_CONDITION` use, for storing this gotchi's
+    /// latest harvest when it was last harvested at `from` before that.
+    pub(crate) fn harvest_values(&self, from: SystemTime) -> Vec<(&'static str, AttributeValue)> {
+        vec![
+            (
+                ":last_harvest",
+                s(&format_rfc3339(self.last_harvest).to_string()),
+            ),
+            (":harvested_from", s(&format_rfc3339(from).to_string())),
+            (
+                ":harvest_log",
+                AttributeValue {
+                    l: Some(
+                        self.harvest_log
+                            .iter()
+                            .cloned()
+                            .map(|gho| gho.into())
+                            .collect(),
+                    ),
+                    ..Default::default()
+                },
+            ),
+        ]
+    }
+}
+
+impl Possession {
+    /// Harvests this possession if it's a gotchi; see `Possessed::<Gotchi>::harvest`.
+    pub fn harvest(&mut self, now: SystemTime) -> Option<Harvest> {
+        let mut gotchi = Possessed::<Gotchi>::from_possession(self.clone())?;
+        let harvest = gotchi.harvest(now);
+        *self = gotchi.into_possession();
+        Some(harvest)
+    }
+
+    /// Harvests a copy of this possession if it's a gotchi, so that it can be harvested in the
+    /// same update which hands it over to someone else. Along with the Harvest, returns the values
+    /// `HARVEST_SET` and `HARVEST_CONDITION` need to be added to that update.
+    pub(crate) fn harvest_update(
+        &self,
+        now: SystemTime,
+    ) -> Option<(Harvest, Vec<(&'static str, AttributeValue)>)> {
+        let from = self.kind.gotchi()?.last_harvest;
+        let mut harvested = self.clone();
+        let harvest = harvested.harvest(now)?;
+        Some((harvest, harvested.kind.gotchi()?.harvest_values(from)))
     }
 }
+
+impl Harvest {
+    /// The entry crediting this harvest to the gotchi's owner.
+    pub fn entry(&self) -> LedgerEntry {
+        LedgerEntry::credit(self.amount, LedgerReason::Harvest(self.gotchi))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum HarvestError {
+    /// The gotchi was harvested or changed hands since it was loaded.
+    Conflict,
+    /// Contains: why the harvest couldn't be stored in the database
+    Db(String),
+}
+impl fmt::Display for HarvestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use HarvestError::*;
+        match self {
+            Conflict => write!(
+                f,
+                "this gotchi was harvested or changed hands in the meantime"
+            ),
+            Db(e) => write!(f, "couldn't store harvest: {}", e),
+        }
+    }
+}
+
+impl Possessed<Gotchi> {
+    /// Collects everything this gotchi has earned since it was last harvested,
+    /// recording it in the harvest log under whoever owns the gotchi now.
+    /// Time left over that doesn't make up a whole harvest interval
+    /// carries over to the next harvest.
+    pub fn harvest(&mut self, now: SystemTime) -> Harvest {
+        let (intervals, amount) = self.inner.unharvested(now);
+        self.inner.last_harvest += HARVEST_INTERVAL * intervals;
+        self.log_harvest(amount)
+    }
+
+    fn log_harvest(&mut self, amount: u64) -> Harvest {
+        let steader = &self.steader;
+        match self.inner.harvest_log.iter_mut().find(|o| o.id == *steader) {
+            Some(owner) => owner.harvested += amount,
+            None => self.inner.harvest_log.push(GotchiHarvestOwner {
+                id: self.steader.clone(),
+                harvested: amount,
+            }),
+        }
+
+        Harvest {
+            gotchi: self.id,
+            owner: self.steader.clone(),
+            amount,
+        }
+    }
+
+    /// Makes this gotchi happier, on top of however happy it is at the given time.
+    /// Whatever it earned up until then is harvested first, at the happiness it had
+    /// while earning it, so callers should credit the returned Harvest. That includes
+    /// the part of the current harvest interval that's already passed, so the next
+    /// interval begins right as the gotchi is boosted.
+    pub fn boost_happiness(&mut self, boost: u64, now: SystemTime) -> Harvest {
+        let (intervals, whole) = self.inner.unharvested(now);
+        self.inner.last_harvest += HARVEST_INTERVAL * intervals;
+
+        let partial = now
+            .duration_since(self.inner.last_harvest)
+            .unwrap_or_default()
+            .as_nanos();
+        let earned_partially = (self.inner.happiness_at(self.inner.last_harvest) as u128 * partial
+            / HARVEST_INTERVAL.as_nanos()) as u64;
+        self.inner.last_harvest = self.inner.last_harvest.max(now);
+
+        self.inner.happiness = self.inner.happiness_at(now) + boost;
+        self.inner.happiness_updated = now;
+        self.log_harvest(whole + earned_partially)
+    }
+
+    /// Harvests this gotchi and credits its owner in a single transaction, so that
+    /// nothing it earned is ever paid out twice. This copy of the gotchi is harvested
+    /// either way, so if it was harvested or changed hands in the meantime
+    /// it should be fetched again.
+    pub async fn harvest_db(
+        &mut self,
+        db: &DynamoDbClient,
+        now: SystemTime,
+    ) -> Result<Harvest, HarvestError> {
+        let from = self.inner.last_harvest;
+        let harvest = self.harvest(now);
+
+        let mut harvest_values = self.inner.harvest_values(from);
+        harvest_values.push((":steader", s(&self.steader)));
+        let mut transact_items = vec![rusoto_dynamodb::TransactWriteItem {
+            update: Some(rusoto_dynamodb::Update {
+                table_name: TABLE_NAME.to_string(),
+                key: Key::gotchi(self.id).into_item(),
+                update_expression: format!("SET {}", HARVEST_SET),
+                condition_expression: Some(format!("steader = :steader AND {}", HARVEST_CONDITION)),
+                expression_attribute_values: values(harvest_values),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }];
+        if harvest.amount > 0 {
+            transact_items.extend(Profile::balance_transaction(
+                harvest.owner.clone(),
+                vec![harvest.entry()],
+            ));
+        }
+
+        db.transact_write_items(rusoto_dynamodb::TransactWriteItemsInput {
+            transact_items,
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| match e {
+            rusoto_core::RusotoError::Service(
+                rusoto_dynamodb::TransactWriteItemsError::TransactionCanceled(_),
+            ) => HarvestError::Conflict,
+            e => HarvestError::Db(e.to_string()),
+        })?;
+
+        Ok(harvest)
+    }
+
+    /// Feeds this gotchi an item, which is used up, so callers should delete it.
+    /// Like boost_happiness, this harvests the gotchi first.
+    pub fn feed(&mut self, food: &Possession, now: SystemTime) -> Result<Harvest, FeedError> {
+        let boost = self
+            .inner
+            .foods
+            .iter()
+            .find(|(name, _)| *name == food.name)
+            .map(|&(_, boost)| boost)
+            .ok_or_else(|| FeedError::NotFood(self.inner.nickname.clone(), food.name.clone()))?;
+        if food.sale.is_some() {
+            return Err(FeedError::OnSale);
+        }
+
+        Ok(self.boost_happiness(boost, now))
+    }
+}
+/// What came out of an egg when it hatched.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hatch {
+    /// The egg, which is used up by hatching and should be deleted.
+    pub egg: uuid::Uuid,
+    pub hatched: Vec<Possession>,
+    /// How good of a roll this was; 0.0 is the worst possible hatch and 1.0 the best.
+    pub percentile: f32,
+}
+
+#[derive(Debug, Clone)]
+pub enum HatchError {
+    /// Contains: the name of the gotchi which has no hatch table
+    NotAnEgg(String),
+    UnknownHatch(ConfigError),
+}
+impl fmt::Display for HatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use HatchError::*;
+        match self {
+            NotAnEgg(name) => write!(f, "{} isn't an egg, so it can't be hatched", name),
+            UnknownHatch(e) => write!(f, "egg hatches into something unknown: {}", e),
+        }
+    }
+}
+impl From<ConfigError> for HatchError {
+    fn from(e: ConfigError) -> Self {
+        HatchError::UnknownHatch(e)
+    }
+}
+
+impl Possessed<Gotchi> {
+    /// Rolls this egg's hatch table, using the egg up in the process.
+    /// Everything that hatches belongs to whoever owned the egg.
+    pub fn hatch(self, rng: &mut impl rand::Rng) -> Result<Hatch, HatchError> {
+        match &self.inner.hatch_table {
+            Some(table) => self.hatch_from(table, rng),
+            None => Err(HatchError::NotAnEgg(self.name.clone())),
+        }
+    }
+
+    fn hatch_from(&self, table: &LootTable, rng: &mut impl rand::Rng) -> Result<Hatch, HatchError> {
+        let (names, percentile) = config::spawn_with_percentile(table, rng);
+
+        Ok(Hatch {
+            hatched: names
+                .iter()
+                .map(|name| {
+                    Ok(Possession::new(
+                        CONFIG.find_possession_handle(name)?,
+                        Owner::hatcher(self.steader.clone()),
+                    ))
+                })
+                .collect::<Result<_, ConfigError>>()?,
+            egg: self.id,
+            percentile,
+        })
+    }
+}
+
+#[test]
+fn egg_hatch() {
+    use config::CountProbability;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let egg = |name| -> Possessed<Gotchi> {
+        Possessed::from_possession(Possession::new(
+            CONFIG.find_possession_handle(&name).unwrap(),
+            Owner::farmer("bob".to_string()),
+        ))
+        .unwrap()
+    };
+    let table: LootTable = vec![
+        (CountProbability(0.9, (1.0, 1.5)), "Cyl Crystal".to_string()),
+        (CountProbability(0.5, (1.0, 2.0)), "Gempheus".to_string()),
+    ];
+
+    let cyl_egg = egg("Cyl Egg");
+    let egg_id = cyl_egg.id;
+    let hatch = cyl_egg
+        .hatch_from(&table, &mut StdRng::seed_from_u64(4))
+        .unwrap();
+
+    assert_eq!(hatch.egg, egg_id);
+    assert!(hatch.hatched.len() <= 4);
+    assert!(hatch
+        .hatched
+        .iter()
+        .all(|p| p.ownership_log == vec![Owner::hatcher("bob".to_string())]));
+    assert!(hatch.percentile >= 0.0 && hatch.percentile <= 1.0);
+
+    let unknown: LootTable = vec![(
+        CountProbability(0.99, (1.0, 1.5)),
+        "Not A Thing".to_string(),
+    )];
+    assert!(egg("Cyl Egg")
+        .hatch_from(&unknown, &mut StdRng::seed_from_u64(4))
+        .is_err());
+}
+
 #[test]
 fn gotchi_serialize() -> Result<(), AttributeParseError> {
     let og = Gotchi::new(
@@ -180,3 +646,175 @@ fn gotchi_serialize() -> Result<(), AttributeParseError> {
 
     Ok(())
 }
+
+#[test]
+fn gotchi_happiness() -> Result<(), AttributeParseError> {
+    use std::time::Duration;
+
+    let hour = Duration::from_secs(60 * 60);
+    let start = SystemTime::UNIX_EPOCH + hour * 24 * 365 * 50;
+    let gempheus = CONFIG.find_possession_handle(&"Gempheus").unwrap();
+
+    let mut gotchi: Possessed<Gotchi> =
+        Possessed::from_possession(Possession::new(gempheus, Owner::farmer("bob".to_string())))
+            .unwrap();
+    gotchi.inner.happiness_updated = start;
+    gotchi.inner.last_harvest = start;
+    let base = gotchi.inner.base_happiness;
+    assert_eq!(gotchi.inner.plant_effect_strength(start), 1.0);
+
+    let decay = gotchi.inner.happiness_decay;
+    gotchi.boost_happiness(decay * 2, start);
+    assert_eq!(gotchi.inner.happiness_at(start + hour), base + decay);
+    // never less happy than when it started out
+    assert_eq!(gotchi.inner.happiness_at(start + hour * 1000), base);
+    assert!(gotchi.inner.plant_effect_strength(start) > 1.0);
+    assert!(gotchi.inner.plant_effect_strength(start) <= MAX_PLANT_EFFECT_STRENGTH);
+
+    let rock = Possession::new(
+        CONFIG.find_possession_handle(&"Land Deed").unwrap(),
+        Owner::farmer("bob".to_string()),
+    );
+    assert!(gotchi.feed(&rock, start).is_err());
+
+    let mut item = Item::new();
+    gotchi.inner.write_item(&mut item);
+    let mut copy = Gotchi::default();
+    copy.fill_from_item(&item)?;
+    assert_eq!(copy.happiness, gotchi.inner.happiness);
+    assert_eq!(copy.happiness_updated, gotchi.inner.happiness_updated);
+
+    Ok(())
+}
+
+#[test]
+fn gotchi_harvest() -> Result<(), AttributeParseError> {
+    let start = SystemTime::UNIX_EPOCH + HARVEST_INTERVAL * 24 * 365 * 50;
+    let mut gotchi: Possessed<Gotchi> = Possessed::from_possession(Possession::new(
+        CONFIG.find_possession_handle(&"Gempheus").unwrap(),
+        Owner::farmer("bob".to_string()),
+    ))
+    .unwrap();
+    gotchi.inner.happiness_updated = start;
+    gotchi.inner.last_harvest = start;
+    let base = gotchi.inner.base_happiness;
+
+    // partial intervals carry over
+    let harvest = gotchi.harvest(start + HARVEST_INTERVAL * 3 / 2);
+    assert_eq!(harvest.amount, base);
+    assert_eq!(harvest.owner, "bob");
+    assert_eq!(gotchi.inner.last_harvest, start + HARVEST_INTERVAL);
+    assert_eq!(gotchi.harvest(start + HARVEST_INTERVAL * 3 / 2).amount, 0);
+
+    // happier gotchis earn more, until their happiness wears off
+    let decay = gotchi.inner.happiness_decay;
+    let harvest = gotchi.boost_happiness(decay * 2, start + HARVEST_INTERVAL);
+    assert_eq!(harvest.amount, 0);
+    let harvest = gotchi.harvest(start + HARVEST_INTERVAL * 11);
+    assert_eq!(harvest.amount, base * 10 + decay * 2 + decay);
+    assert_eq!(
+        gotchi.inner.harvest_log,
+        vec![GotchiHarvestOwner {
+            id: "bob".to_string(),
+            harvested: base * 11 + decay * 3,
+        }]
+    );
+
+    // new owners get their own entry in the log
+    gotchi.steader = "alice".to_string();
+    gotchi.harvest(start + HARVEST_INTERVAL * 12);
+    assert_eq!(gotchi.inner.harvest_log.len(), 2);
+    assert_eq!(gotchi.inner.harvest_log[1].harvested, base);
+
+    let mut item = Item::new();
+    gotchi.inner.write_item(&mut item);
+    let mut copy = Gotchi::default();
+    copy.fill_from_item(&item)?;
+    assert_eq!(copy.last_harvest, gotchi.inner.last_harvest);
+
+    Ok(())
+}
+
+#[test]
+fn boosts_split_the_interval_they_happen_in() {
+    let start = SystemTime::UNIX_EPOCH + HARVEST_INTERVAL * 24 * 365 * 50;
+    let mut gotchi: Possessed<Gotchi> = Possessed::from_possession(Possession::new(
+        CONFIG.find_possession_handle(&"Gempheus").unwrap(),
+        Owner::farmer("bob".to_string()),
+    ))
+    .unwrap();
+    gotchi.inner.happiness_updated = start;
+    gotchi.inner.last_harvest = start;
+    let base = gotchi.inner.base_happiness;
+    let boost = gotchi.inner.happiness_decay * 10;
+
+    // the first hour and a half are paid out at the old happiness
+    let boosted_at = start + HARVEST_INTERVAL * 3 / 2;
+    let harvest = gotchi.boost_happiness(boost, boosted_at);
+    assert_eq!(harvest.amount, base + base / 2);
+    assert_eq!(gotchi.inner.last_harvest, boosted_at);
+
+    // and every whole interval after that at the new one
+    let harvest = gotchi.harvest(boosted_at + HARVEST_INTERVAL * 3 / 2);
+    assert_eq!(harvest.amount, base + boost);
+    assert_eq!(gotchi.inner.last_harvest, boosted_at + HARVEST_INTERVAL);
+}
+
+#[test]
+fn unharvested_in_closed_form() {
+    let start = SystemTime::UNIX_EPOCH + HARVEST_INTERVAL * 24 * 365 * 50;
+    let mut gotchi = Gotchi::new(CONFIG.find_possession_handle(&"Gempheus").unwrap(), "bob");
+    let hourly = |gotchi: &Gotchi, now: SystemTime| -> u64 {
+        let (intervals, _) = gotchi.unharvested(now);
+        (0..intervals)
+            .map(|i| gotchi.happiness_at(gotchi.last_harvest + HARVEST_INTERVAL * i))
+            .sum()
+    };
+
+    let base = gotchi.base_happiness;
+    let minute = Duration::from_secs(60);
+    for &happiness in &[0, base / 2, base, base + 1, base * 3 + 7] {
+        for &updated in &[
+            start - minute * 150,
+            start,
+            start + minute * 30,
+            start + minute * 200,
+        ] {
+            gotchi.happiness = happiness;
+            gotchi.happiness_updated = updated;
+            gotchi.last_harvest = start;
+            for hours in 0..(base * 3 + 20) as u32 {
+                let now = start + HARVEST_INTERVAL * hours + minute;
+                assert_eq!(
+                    gotchi.unharvested(now).1,
+                    hourly(&gotchi, now),
+                    "happiness {}, updated {:?}, {} hours",
+                    happiness,
+                    updated,
+                    hours
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn boosts_dont_change_past_earnings() {
+    let start = SystemTime::UNIX_EPOCH + HARVEST_INTERVAL * 24 * 365 * 50;
+    let mut idle: Possessed<Gotchi> = Possessed::from_possession(Possession::new(
+        CONFIG.find_possession_handle(&"Gempheus").unwrap(),
+        Owner::farmer("bob".to_string()),
+    ))
+    .unwrap();
+    idle.inner.happiness_updated = start;
+    idle.inner.last_harvest = start;
+    let mut fed = idle.clone();
+
+    // feeding goes through boost_happiness
+    let later = start + HARVEST_INTERVAL * 5;
+    let boost = fed.inner.happiness_decay * 10;
+    let harvest = fed.boost_happiness(boost, later);
+    assert_eq!(harvest.amount, idle.harvest(later).amount);
+    assert_eq!(harvest.amount, idle.inner.base_happiness * 5);
+    assert_eq!(fed.harvest(later).amount, 0);
+}
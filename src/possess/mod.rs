@@ -8,7 +8,7 @@ pub mod gotchi;
 mod keepsake;
 pub mod seed;
 
-pub use gotchi::Gotchi;
+pub use gotchi::{Gotchi, Harvest, Hatch, HatchError};
 pub use keepsake::Keepsake;
 pub use seed::Seed;
 
@@ -166,6 +166,12 @@ impl Owner {
             acquisition: Acquisition::Hatched,
         }
     }
+    pub fn trader(id: String) -> Self {
+        Self {
+            id,
+            acquisition: Acquisition::Trade,
+        }
+    }
     fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
         use AttributeParseError::*;
 
@@ -491,6 +497,9 @@ impl Possession {
             },
         );
         self.kind.write_item(&mut m);
+        if let Some(sale) = &self.sale {
+            sale.write_item(&mut m);
+        }
         m
     }
 
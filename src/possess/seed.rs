@@ -1,4 +1,4 @@
-use super::{Possessable, PossessionKind};
+use super::{Owner, Possessable, Possession, PossessionKind};
 use crate::{config, AttributeParseError, Item, CONFIG};
 use config::{ArchetypeHandle, ArchetypeKind};
 use rusoto_dynamodb::AttributeValue;
@@ -27,6 +27,23 @@ impl SeedGrower {
         SeedGrower { id, generations }
     }
 
+    /// The pedigree of seeds grown by `grower` from a plant with the given pedigree.
+    /// If the grower is the one who grew the last generation, they get another generation,
+    /// otherwise they're added onto the end of the pedigree.
+    pub fn grow_pedigree(pedigree: &[SeedGrower], grower: &str) -> Vec<SeedGrower> {
+        let mut pedigree = pedigree.to_vec();
+        match pedigree.last_mut() {
+            Some(last) if last.id == grower => last.generations += 1,
+            _ => pedigree.push(SeedGrower::new(grower.to_string(), 1)),
+        }
+        pedigree
+    }
+
+    /// How many generations of seeds every grower in this pedigree grew altogether.
+    pub fn total_generations(pedigree: &[SeedGrower]) -> u64 {
+        pedigree.iter().map(|sg| sg.generations).sum()
+    }
+
     pub fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
         use AttributeParseError::*;
 
@@ -103,6 +120,13 @@ impl Seed {
             }],
         }
     }
+    /// Turns this seed into a possession, keeping its pedigree.
+    pub fn into_possession(self, owner: Owner) -> Possession {
+        let mut possession = Possession::new(self.archetype_handle, owner);
+        possession.kind = PossessionKind::Seed(self);
+        possession
+    }
+
     pub fn fill_from_item(&mut self, item: &Item) -> Result<(), AttributeParseError> {
         use AttributeParseError::*;
 
@@ -137,3 +161,22 @@ impl Seed {
         );
     }
 }
+
+#[test]
+fn pedigree_generations() {
+    let bob = SeedGrower::new("bob".to_string(), 0);
+
+    let pedigree = SeedGrower::grow_pedigree(&[bob.clone()], "bob");
+    assert_eq!(pedigree, vec![SeedGrower::new("bob".to_string(), 1)]);
+
+    let pedigree = SeedGrower::grow_pedigree(&pedigree, "alice");
+    let pedigree = SeedGrower::grow_pedigree(&pedigree, "alice");
+    assert_eq!(
+        pedigree,
+        vec![
+            SeedGrower::new("bob".to_string(), 1),
+            SeedGrower::new("alice".to_string(), 2)
+        ]
+    );
+    assert_eq!(SeedGrower::total_generations(&pedigree), 3);
+}
@@ -0,0 +1,348 @@
+//! Deterministically simulates a plant growing over time, so that
+//! content can be balanced without having to wait around for plants to grow.
+//!
+//! Instead of rolling dice, the simulation works with expected values:
+//! a yield which has a 50% chance of producing one Bread Essence adds half of a
+//! Bread Essence to the running total. This means two simulations of the same
+//! plant over the same horizon always produce identical reports.
+use crate::config::{
+    ArchetypeHandle, CountProbability, PlantAdvancement, PlantAdvancementSum, PlantArchetype, Yield,
+};
+use crate::CONFIG;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct SimulationOptions {
+    /// How long to simulate the plant growing for.
+    pub horizon: Duration,
+    /// How long a single farm cycle lasts.
+    pub farm_cycle: Duration,
+    /// The plant's xp is recorded every this many farm cycles.
+    pub xp_sample_interval: u64,
+    /// Advancements which apply to the plant on top of the ones it unlocks,
+    /// i.e. those granted by gotchis or keepsakes.
+    pub extra_advancements: Vec<PlantAdvancement>,
+}
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        let farm_cycle = Duration::from_millis(crate::FARM_CYCLE_MILLIS);
+
+        Self {
+            horizon: Duration::from_secs(60 * 60 * 24 * 7),
+            farm_cycle,
+            xp_sample_interval: (60 * 60 * 1000) / crate::FARM_CYCLE_MILLIS,
+            extra_advancements: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AdvancementReached {
+    pub title: String,
+    /// How much xp the plant must have to reach this advancement.
+    pub xp: u64,
+    /// How many farm cycles it took to get here.
+    pub cycle: u64,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemRate {
+    pub archetype_handle: ArchetypeHandle,
+    pub name: String,
+    /// How many of this item are expected over the entire horizon.
+    pub expected_total: f32,
+    /// The expected total averaged over every hour of the horizon.
+    pub per_hour: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CraftThroughput {
+    pub title: String,
+    /// Farm cycles taken by a single craft, after crafting speed multipliers.
+    pub cycles_per_craft: f32,
+    pub crafts_per_hour: f32,
+    /// How many of each item a single craft is expected to produce,
+    /// including the chance of double yields.
+    pub expected_makes: Vec<(String, f32)>,
+    /// How many of each item a single craft uses up,
+    /// after the chance of the ingredients being returned.
+    pub expected_needs: Vec<(String, f32)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationReport {
+    pub plant: String,
+    pub cycles: u64,
+    pub horizon: Duration,
+    pub final_xp: u64,
+    /// (farm cycle, xp) pairs, sampled every `xp_sample_interval` cycles.
+    pub xp_curve: Vec<(u64, u64)>,
+    pub advancements: Vec<AdvancementReached>,
+    pub items: Vec<ItemRate>,
+    /// Throughput for each of the recipes the plant knows at the end of the horizon.
+    pub crafts: Vec<CraftThroughput>,
+}
+
+fn possession_name(ah: ArchetypeHandle) -> String {
+    CONFIG
+        .possession_archetypes
+        .get(ah)
+        .map(|a| a.name.clone())
+        .unwrap_or_else(|| format!("unknown archetype {}", ah))
+}
+
+/// How much xp a yield is expected to award each time it is rolled,
+/// taking into account that each subsequent item awards less xp according to `dropoff`.
+pub(crate) fn expected_yield_xp(y: &Yield<ArchetypeHandle>) -> f32 {
+    let (lo, hi) = y.xp;
+    // xp is rolled from lo up to but not including hi
+    let average_xp = if hi > lo {
+        (lo + hi - 1) as f32 / 2.0
+    } else {
+        lo as f32
+    };
+
+    // the chance of getting at least n items is the chance that the nth item awards xp
+    let odds = CountProbability(y.chance, y.amount).count_distribution();
+    (1..odds.len())
+        .map(|n| {
+            let at_least: f32 = odds[n..].iter().sum();
+            at_least * average_xp / (n as f32 * y.dropoff)
+        })
+        .sum()
+}
+
+fn craft_throughput(sum: &PlantAdvancementSum, farm_cycle: Duration) -> Vec<CraftThroughput> {
+    let cycles_per_hour = 60.0 * 60.0 / farm_cycle.as_secs_f32();
+
+    sum.recipes
+        .iter()
+        .map(|recipe| {
+            let cycles_per_craft = recipe.time / sum.crafting_speed_multiplier;
+
+            let makes = crate::odds::recipe_odds(&recipe.makes);
+
+            CraftThroughput {
+                title: recipe
+                    .clone()
+                    .lookup_handles()
+                    .map(|r| r.title())
+                    .unwrap_or_else(|| "Unknown Recipe".to_string()),
+                cycles_per_craft,
+                crafts_per_hour: cycles_per_hour / cycles_per_craft,
+                expected_makes: makes
+                    .into_iter()
+                    .map(|(ah, odds)| {
+                        (
+                            possession_name(ah),
+                            odds.mean() * (1.0 + sum.double_craft_yield_chance),
+                        )
+                    })
+                    .collect(),
+                expected_needs: recipe
+                    .needs
+                    .iter()
+                    .map(|&(n, ah)| {
+                        (
+                            possession_name(ah),
+                            n as f32 * (1.0 - sum.craft_return_chance).max(0.0),
+                        )
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Grows a plant of the given archetype from scratch for `opts.horizon`,
+/// tallying what it is expected to produce along the way.
+pub fn simulate(plant: &PlantArchetype, opts: &SimulationOptions) -> SimulationReport {
+    let advancements = &plant.advancements;
+    let cycles = (opts.horizon.as_millis() / opts.farm_cycle.as_millis().max(1)) as u64;
+    let sample_interval = opts.xp_sample_interval.max(1);
+    let sum_for = |xp: u64| advancements.sum(xp, opts.extra_advancements.iter());
+
+    // xp is tracked as a float because yields award fractions of xp on average
+    let mut xp: f64 = 0.0;
+    let mut position = advancements.current_position(0);
+    let mut sum = sum_for(0);
+    let mut until_yield = plant.base_yield_duration.unwrap_or(0.0);
+    let mut items: BTreeMap<ArchetypeHandle, f32> = BTreeMap::new();
+
+    let mut report = SimulationReport {
+        plant: plant.name.clone(),
+        cycles,
+        horizon: opts.horizon,
+        final_xp: 0,
+        xp_curve: vec![(0, 0)],
+        advancements: vec![AdvancementReached {
+            title: advancements.base.title.clone(),
+            xp: 0,
+            cycle: 0,
+            elapsed: Duration::from_secs(0),
+        }],
+        items: vec![],
+        crafts: vec![],
+    };
+
+    for cycle in 1..=cycles {
+        let ticks = 1 + sum.total_extra_time_ticks;
+        xp += (sum.xp_multiplier as u128 * ticks) as f64;
+
+        if let Some(base_yield_duration) = plant.base_yield_duration {
+            until_yield -= sum.yield_speed_multiplier * ticks as f32;
+            while until_yield <= 0.0 {
+                for y in sum.yields.iter() {
+                    *items.entry(y.yields).or_insert(0.0) +=
+                        CountProbability(y.chance, y.amount).expected_count();
+                    xp += expected_yield_xp(y) as f64;
+                }
+                until_yield += base_yield_duration.max(1.0);
+            }
+        }
+
+        let whole_xp = xp as u64;
+        let new_position = advancements.current_position(whole_xp);
+        if new_position != position {
+            for reached in position + 1..=new_position {
+                if let Some(adv) = advancements.get(reached) {
+                    report.advancements.push(AdvancementReached {
+                        title: adv.title.clone(),
+                        xp: advancements.required_xp(reached).unwrap_or(0),
+                        cycle,
+                        elapsed: opts.farm_cycle.mul_f64(cycle as f64),
+                    });
+                }
+            }
+            position = new_position;
+            sum = sum_for(whole_xp);
+        }
+
+        if cycle % sample_interval == 0 {
+            report.xp_curve.push((cycle, whole_xp));
+        }
+    }
+
+    let hours = opts.horizon.as_secs_f32() / (60.0 * 60.0);
+    report.final_xp = xp as u64;
+    report.items = items
+        .into_iter()
+        .map(|(archetype_handle, expected_total)| ItemRate {
+            archetype_handle,
+            name: possession_name(archetype_handle),
+            expected_total,
+            per_hour: if hours > 0.0 {
+                expected_total / hours
+            } else {
+                0.0
+            },
+        })
+        .collect();
+    report.crafts = craft_throughput(&sum, opts.farm_cycle);
+
+    report
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} grown for {} ({} farm cycles), ending with {}xp",
+            self.plant,
+            humantime::format_duration(self.horizon),
+            self.cycles,
+            self.final_xp
+        )?;
+
+        writeln!(f, "\nadvancements:")?;
+        for adv in self.advancements.iter() {
+            writeln!(
+                f,
+                "  {:>8}xp after {:<16} {}",
+                adv.xp,
+                humantime::format_duration(adv.elapsed).to_string(),
+                adv.title
+            )?;
+        }
+
+        writeln!(f, "\nexpected items:")?;
+        for item in self.items.iter() {
+            writeln!(
+                f,
+                "  {:>10.2} {} ({:.2} per hour)",
+                item.expected_total, item.name, item.per_hour
+            )?;
+        }
+
+        writeln!(f, "\ncrafting:")?;
+        for craft in self.crafts.iter() {
+            writeln!(
+                f,
+                "  {}: {:.1} cycles per craft, {:.3} crafts per hour",
+                craft.title, craft.cycles_per_craft, craft.crafts_per_hour
+            )?;
+            for (name, n) in craft.expected_needs.iter() {
+                writeln!(f, "    uses  {:.2} {}", n, name)?;
+            }
+            for (name, n) in craft.expected_makes.iter() {
+                writeln!(f, "    makes {:.2} {}", n, name)?;
+            }
+        }
+
+        writeln!(f, "\nxp curve:")?;
+        for (cycle, xp) in self.xp_curve.iter() {
+            writeln!(f, "  {:>10} cycles: {}xp", cycle, xp)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn expected_yield_xp_is_the_mean_roll() {
+    let one = |xp| Yield {
+        chance: 1.0,
+        amount: (1.0, 1.0),
+        dropoff: 1.0,
+        xp,
+        yields: 0,
+    };
+    // rolls of 1, 2 and 3
+    assert!((expected_yield_xp(&one((1, 4))) - 2.0).abs() < 0.0001);
+    assert!((expected_yield_xp(&one((5, 6))) - 5.0).abs() < 0.0001);
+    assert!((expected_yield_xp(&one((5, 5))) - 5.0).abs() < 0.0001);
+
+    let half = Yield {
+        chance: 0.5,
+        ..one((2, 5))
+    };
+    assert!((expected_yield_xp(&half) - 1.5).abs() < 0.0001);
+}
+
+#[test]
+fn simulation_is_deterministic() {
+    let opts = SimulationOptions {
+        horizon: Duration::from_secs(60 * 60 * 24 * 3),
+        ..Default::default()
+    };
+
+    for plant in CONFIG.plant_archetypes.iter() {
+        let report = simulate(plant, &opts);
+        assert_eq!(report, simulate(plant, &opts));
+
+        assert!(
+            report.xp_curve.windows(2).all(|w| w[0].1 <= w[1].1),
+            "{}'s xp went down over time",
+            plant.name
+        );
+        assert!(
+            report.advancements.windows(2).all(|w| w[0].xp < w[1].xp),
+            "{}'s advancements weren't reached in order",
+            plant.name
+        );
+    }
+}
@@ -0,0 +1,1032 @@
+//! Lets Hacksteaders offer each other possessions and gp directly, without going through a market.
+use crate::currency::{BalanceError, LedgerEntry, LedgerReason};
+use crate::market::{n, s, values};
+use crate::models::Hacksteader;
+use crate::possess::gotchi::{HARVEST_CONDITION, HARVEST_SET};
+use crate::possess::{self, Owner};
+use crate::{AttributeParseError, Category, Item, Key, Possessed, Possession, Profile, TABLE_NAME};
+use humantime::{format_rfc3339, parse_rfc3339};
+use rusoto_dynamodb::{AttributeValue, DynamoDb, DynamoDbClient};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// DynamoDB won't write more than this many items in a single transaction.
+const MAX_TRANSACTION_ITEMS: usize = 25;
+
+/// How many possessions each side of a trade can put up at most, so that accepting it fits
+/// in one transaction. That writes the offer, each possession, a ledger entry for each gotchi
+/// harvested on its way to its new owner, and both profiles and their ledger entries for gp.
+pub const MAX_TRADE_POSSESSIONS: usize = (MAX_TRANSACTION_ITEMS - 5) / 4;
+
+/// What one Hacksteader puts up in a trade.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TradeSide {
+    pub steader: String,
+    pub possessions: Vec<Key>,
+    pub gp: u64,
+}
+impl TradeSide {
+    /// Stores this side of an offer under fields starting with the given prefix.
+    fn write_item(&self, item: &mut Item, prefix: &str) {
+        item.insert(format!("{}_steader", prefix), s(&self.steader));
+        item.insert(
+            format!("{}_possessions", prefix),
+            AttributeValue {
+                l: Some(
+                    self.possessions
+                        .iter()
+                        .map(|k| AttributeValue {
+                            m: Some(k.into_item()),
+                            ..Default::default()
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+        );
+        item.insert(format!("{}_gp", prefix), n(self.gp));
+    }
+
+    /// Contains: the names of the steader, possessions and gp fields `write_item` made
+    fn from_item(item: &Item, fields: [&'static str; 3]) -> Result<Self, AttributeParseError> {
+        use AttributeParseError::*;
+
+        let [steader, possessions, gp] = fields;
+        let field = |name: &'static str| item.get(name).ok_or(MissingField(name));
+        Ok(Self {
+            steader: field(steader)?
+                .s
+                .as_ref()
+                .ok_or(WronglyTypedField(steader))?
+                .clone(),
+            possessions: field(possessions)?
+                .l
+                .as_ref()
+                .ok_or(WronglyTypedField(possessions))?
+                .iter()
+                .map(|k| Key::from_item(k.m.as_ref().ok_or(WronglyTypedField(possessions))?))
+                .collect::<Result<_, _>>()?,
+            gp: field(gp)?
+                .n
+                .as_ref()
+                .ok_or(WronglyTypedField(gp))?
+                .parse()
+                .map_err(|e| IntFieldParse(gp, e))?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TradeStatus {
+    Open,
+    Accepted,
+    Declined,
+    /// Contains: the id of the offer made in response to this one
+    Countered(uuid::Uuid),
+    Expired,
+}
+impl TradeStatus {
+    fn tag(&self) -> &'static str {
+        use TradeStatus::*;
+        match self {
+            Open => "open",
+            Accepted => "accepted",
+            Declined => "declined",
+            Countered(_) => "countered",
+            Expired => "expired",
+        }
+    }
+
+    /// Moves the stored offer with the given id to this status,
+    /// as long as it's still open in the database.
+    fn update(self, offer: uuid::Uuid) -> rusoto_dynamodb::Update {
+        let mut attribute_values = vec![
+            (":open", s(TradeStatus::Open.tag())),
+            (":status", s(self.tag())),
+        ];
+        let update_expression = match self {
+            TradeStatus::Countered(by) => {
+                attribute_values.push((":countered_by", s(&by.to_string())));
+                "SET trade_status = :status, countered_by = :countered_by"
+            }
+            _ => "SET trade_status = :status",
+        };
+
+        rusoto_dynamodb::Update {
+            table_name: TABLE_NAME.to_string(),
+            key: TradeOffer::key(offer).into_item(),
+            update_expression: update_expression.to_string(),
+            condition_expression: Some("trade_status = :open".to_string()),
+            expression_attribute_values: values(attribute_values),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TradeError {
+    /// Trades have to be between two different Hacksteaders.
+    WithSelf,
+    /// Contains: what became of the offer, which can't be responded to anymore
+    NotOpen(TradeStatus),
+    /// Contains: the steader id of whoever tried to respond to an offer that wasn't made to them
+    NotRecipient(String),
+    /// Contains: the steader id of a Hacksteader who didn't make the offer being accepted
+    NotOfferer(String),
+    /// Contains: the id of a possession which was put up in the trade more than once
+    Duplicate(uuid::Uuid),
+    /// One side put up more than `MAX_TRADE_POSSESSIONS`.
+    TooManyPossessions,
+    /// Contains: the id of a possession the Hacksteader offering it no longer has
+    Missing(uuid::Uuid),
+    /// Contains: the id of a possession which is up for sale
+    OnSale(uuid::Uuid),
+    Balance(BalanceError),
+    /// The offer was responded to, or something in it changed hands,
+    /// before this could go through.
+    Conflict,
+    /// Contains: why the database couldn't be updated
+    Db(String),
+}
+impl fmt::Display for TradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TradeError::*;
+        match self {
+            WithSelf => write!(f, "you can't trade with yourself"),
+            NotOpen(status) => write!(f, "this offer is no longer open: {:?}", status),
+            NotRecipient(id) => write!(f, "this offer wasn't made to {}", id),
+            NotOfferer(id) => write!(f, "this offer wasn't made by {}", id),
+            Duplicate(id) => write!(f, "possession {} can only be traded once", id),
+            TooManyPossessions => write!(
+                f,
+                "each side of a trade can put up at most {} possessions",
+                MAX_TRADE_POSSESSIONS
+            ),
+            Missing(id) => write!(f, "possession {} isn't there to trade anymore", id),
+            OnSale(id) => write!(
+                f,
+                "possession {} can't be traded while it's up for sale",
+                id
+            ),
+            Balance(e) => write!(f, "{}", e),
+            Conflict => write!(f, "this offer changed before it could be responded to"),
+            Db(e) => write!(f, "couldn't carry out the trade: {}", e),
+        }
+    }
+}
+impl From<BalanceError> for TradeError {
+    fn from(e: BalanceError) -> Self {
+        TradeError::Balance(e)
+    }
+}
+
+/// A proposal for two Hacksteaders to swap possessions and gp.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TradeOffer {
+    pub id: uuid::Uuid,
+    /// What the Hacksteader making the offer gives up.
+    pub from: TradeSide,
+    /// What the Hacksteader the offer is made to gives up.
+    pub to: TradeSide,
+    pub created: SystemTime,
+    pub expires: SystemTime,
+    pub status: TradeStatus,
+    /// Contains: the id of the offer this one was made in response to
+    pub counters: Option<uuid::Uuid>,
+}
+
+impl TradeOffer {
+    pub fn new(
+        from: TradeSide,
+        to: TradeSide,
+        now: SystemTime,
+        lasts: Duration,
+    ) -> Result<Self, TradeError> {
+        if from.steader == to.steader {
+            return Err(TradeError::WithSelf);
+        }
+        if from.possessions.len().max(to.possessions.len()) > MAX_TRADE_POSSESSIONS {
+            return Err(TradeError::TooManyPossessions);
+        }
+        let mut seen = std::collections::HashSet::new();
+        for key in from.possessions.iter().chain(to.possessions.iter()) {
+            if !seen.insert(key.id) {
+                return Err(TradeError::Duplicate(key.id));
+            }
+        }
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4(),
+            from,
+            to,
+            created: now,
+            expires: now + lasts,
+            status: TradeStatus::Open,
+            counters: None,
+        })
+    }
+
+    /// Marks the offer as expired if it's still open past when it expires,
+    /// returning whether or not it's open now.
+    pub fn expire(&mut self, now: SystemTime) -> bool {
+        if self.status == TradeStatus::Open && now >= self.expires {
+            self.status = TradeStatus::Expired;
+        }
+        self.status == TradeStatus::Open
+    }
+
+    /// Makes sure the offer can still be responded to, and that it's being responded to
+    /// by the Hacksteader it was made to.
+    fn respond(&mut self, by: &str, now: SystemTime) -> Result<(), TradeError> {
+        if !self.expire(now) {
+            return Err(TradeError::NotOpen(self.status));
+        }
+        if by != self.to.steader {
+            return Err(TradeError::NotRecipient(by.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn decline(&mut self, by: &str, now: SystemTime) -> Result<(), TradeError> {
+        self.respond(by, now)?;
+        self.status = TradeStatus::Declined;
+        Ok(())
+    }
+
+    /// Responds to this offer with another one, made by the Hacksteader this offer was made to,
+    /// which lasts as long as this one did.
+    pub fn counter(
+        &mut self,
+        by: &str,
+        gives: (Vec<Key>, u64),
+        wants: (Vec<Key>, u64),
+        now: SystemTime,
+    ) -> Result<TradeOffer, TradeError> {
+        self.respond(by, now)?;
+
+        let lasts = self
+            .expires
+            .duration_since(self.created)
+            .unwrap_or_default();
+        let mut counter = TradeOffer::new(
+            TradeSide {
+                steader: self.to.steader.clone(),
+                possessions: gives.0,
+                gp: gives.1,
+            },
+            TradeSide {
+                steader: self.from.steader.clone(),
+                possessions: wants.0,
+                gp: wants.1,
+            },
+            now,
+            lasts,
+        )?;
+        counter.counters = Some(self.id);
+        self.status = TradeStatus::Countered(counter.id);
+        Ok(counter)
+    }
+
+    /// Only one side of a trade ever pays gp; what both sides offer cancels out.
+    /// Returns who pays, who's paid, and how much.
+    fn gp_transfer(&self) -> Option<(&str, &str, u64)> {
+        use std::cmp::Ordering::*;
+
+        match self.from.gp.cmp(&self.to.gp) {
+            Greater => Some((
+                &self.from.steader,
+                &self.to.steader,
+                self.from.gp - self.to.gp,
+            )),
+            Less => Some((
+                &self.to.steader,
+                &self.from.steader,
+                self.to.gp - self.from.gp,
+            )),
+            Equal => None,
+        }
+    }
+
+    /// Carries out the trade between the Hacksteader who made the offer and the one accepting it.
+    /// Either everything changes hands or, if anything in the offer isn't there to trade,
+    /// nothing does.
+    pub fn accept(
+        &mut self,
+        now: SystemTime,
+        from: &mut Hacksteader,
+        to: &mut Hacksteader,
+    ) -> Result<(), TradeError> {
+        self.respond(&to.user_id, now)?;
+        if from.user_id != self.from.steader {
+            return Err(TradeError::NotOfferer(from.user_id.clone()));
+        }
+
+        for (hs, side) in [(&*from, &self.from), (&*to, &self.to)].iter() {
+            for key in side.possessions.iter() {
+                match hs.holds(key.id) {
+                    None => return Err(TradeError::Missing(key.id)),
+                    Some(true) => return Err(TradeError::OnSale(key.id)),
+                    Some(false) => {}
+                }
+            }
+        }
+        if let Some((payer, _, amount)) = self.gp_transfer() {
+            let has = if payer == from.user_id {
+                from.profile.wallet.gp
+            } else {
+                to.profile.wallet.gp
+            };
+            if has < amount {
+                return Err(BalanceError::Insufficient { needs: amount, has }.into());
+            }
+        }
+
+        // everything's there, so nothing past this point can fail
+        if let Some((payer, _, amount)) = self.gp_transfer() {
+            let (payer, payee) = if payer == from.user_id {
+                (&mut *from, &mut *to)
+            } else {
+                (&mut *to, &mut *from)
+            };
+            payer
+                .profile
+                .wallet
+                .debit(amount, LedgerReason::Trade(self.id))?;
+            payee
+                .profile
+                .wallet
+                .credit(amount, LedgerReason::Trade(self.id));
+        }
+        for key in self.from.possessions.iter() {
+            let p = from.take_possession(key.id, now).expect("checked above");
+            to.give_possession(p);
+        }
+        for key in self.to.possessions.iter() {
+            let p = to.take_possession(key.id, now).expect("checked above");
+            from.give_possession(p);
+        }
+
+        self.status = TradeStatus::Accepted;
+        Ok(())
+    }
+
+    /// Everything that has to happen in the database for this trade to go through,
+    /// none of which will unless the offer is still open there, neither side's possessions
+    /// have changed hands or been put up for sale since the offer was made,
+    /// and whoever owes gp can pay it. The gotchis in the trade, as they are in the database,
+    /// are harvested for whoever's giving them up, as long as they haven't been since.
+    fn accept_transaction<'a>(
+        &'a self,
+        gotchis: &[Possession],
+        now: SystemTime,
+    ) -> Vec<rusoto_dynamodb::TransactWriteItem> {
+        let mut entries: Vec<(&str, Vec<LedgerEntry>)> = vec![];
+        let mut credit = |steader, entry| match entries.iter_mut().find(|(s, _)| *s == steader) {
+            Some((_, e)) => e.push(entry),
+            None => entries.push((steader, vec![entry])),
+        };
+        if let Some((payer, payee, amount)) = self.gp_transfer() {
+            credit(
+                payer,
+                LedgerEntry::debit(amount, LedgerReason::Trade(self.id)),
+            );
+            credit(
+                payee,
+                LedgerEntry::credit(amount, LedgerReason::Trade(self.id)),
+            );
+        }
+
+        let mut hand_over = |key: &Key, giver: &'a str, taker: &str| {
+            let mut update_values = vec![
+                (":taker", s(taker)),
+                (":giver", s(giver)),
+                (
+                    ":owner",
+                    AttributeValue {
+                        l: Some(vec![Owner::trader(taker.to_string()).into()]),
+                        ..Default::default()
+                    },
+                ),
+            ];
+            let harvest = gotchis
+                .iter()
+                .find(|g| g.id == key.id)
+                .and_then(|g| g.harvest_update(now));
+            let (harvest_set, harvest_condition) = match harvest {
+                Some((harvest, harvest_values)) => {
+                    update_values.extend(harvest_values);
+                    if harvest.amount > 0 {
+                        credit(giver, harvest.entry());
+                    }
+                    (
+                        format!(", {}", HARVEST_SET),
+                        format!(" AND {}", HARVEST_CONDITION),
+                    )
+                }
+                None => (String::new(), String::new()),
+            };
+
+            rusoto_dynamodb::TransactWriteItem {
+                update: Some(rusoto_dynamodb::Update {
+                    table_name: TABLE_NAME.to_string(),
+                    key: key.into_item(),
+                    update_expression: format!(
+                        "SET steader = :taker, ownership_log = list_append(ownership_log, :owner){}",
+                        harvest_set
+                    ),
+                    condition_expression: Some(format!(
+                        "steader = :giver AND attribute_not_exists(price){}",
+                        harvest_condition
+                    )),
+                    expression_attribute_values: values(update_values),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        };
+
+        let mut transact_items = vec![rusoto_dynamodb::TransactWriteItem {
+            update: Some(TradeStatus::Accepted.update(self.id)),
+            ..Default::default()
+        }];
+        for key in self.from.possessions.iter() {
+            transact_items.push(hand_over(key, &self.from.steader, &self.to.steader));
+        }
+        for key in self.to.possessions.iter() {
+            transact_items.push(hand_over(key, &self.to.steader, &self.from.steader));
+        }
+        for (steader, entries) in entries {
+            transact_items.extend(Profile::balance_transaction(steader.to_string(), entries));
+        }
+        transact_items
+    }
+
+    /// Carries out the trade in the database all at once, marking the offer as accepted there
+    /// so that it can't be accepted again. Like `accept`, it can only be accepted by
+    /// the Hacksteader it was made to.
+    pub async fn accept_db(
+        &mut self,
+        db: &DynamoDbClient,
+        by: &str,
+        now: SystemTime,
+    ) -> Result<(), TradeError> {
+        self.respond(by, now)?;
+
+        let mut gotchis = vec![];
+        for key in self
+            .from
+            .possessions
+            .iter()
+            .chain(self.to.possessions.iter())
+        {
+            if key.category == Category::Gotchi {
+                gotchis.push(key.fetch_db(db).await.map_err(TradeError::Db)?);
+            }
+        }
+
+        transact(db, self.accept_transaction(&gotchis, now)).await?;
+        self.status = TradeStatus::Accepted;
+        Ok(())
+    }
+
+    pub fn key(id: uuid::Uuid) -> Key {
+        Key {
+            category: Category::TradeOffer,
+            id,
+        }
+    }
+
+    pub fn item(&self) -> Item {
+        let mut item = TradeOffer::key(self.id).into_item();
+        self.from.write_item(&mut item, "from");
+        self.to.write_item(&mut item, "to");
+        item.insert(
+            "created".to_string(),
+            s(&format_rfc3339(self.created).to_string()),
+        );
+        item.insert(
+            "expires".to_string(),
+            s(&format_rfc3339(self.expires).to_string()),
+        );
+        item.insert("trade_status".to_string(), s(self.status.tag()));
+        if let TradeStatus::Countered(by) = self.status {
+            item.insert("countered_by".to_string(), s(&by.to_string()));
+        }
+        if let Some(counters) = self.counters {
+            item.insert("counters".to_string(), s(&counters.to_string()));
+        }
+        item
+    }
+
+    pub fn from_item(item: &Item) -> Result<Self, AttributeParseError> {
+        use AttributeParseError::*;
+
+        let string = |name: &'static str| {
+            item.get(name)
+                .ok_or(MissingField(name))?
+                .s
+                .as_ref()
+                .ok_or(WronglyTypedField(name))
+        };
+        let time =
+            |name: &'static str| parse_rfc3339(string(name)?).map_err(|e| TimeFieldParse(name, e));
+        let id = |name: &'static str| {
+            uuid::Uuid::parse_str(string(name)?).map_err(|e| IdFieldParse(name, e))
+        };
+
+        Ok(Self {
+            id: Key::from_item(item)?.id,
+            from: TradeSide::from_item(item, ["from_steader", "from_possessions", "from_gp"])?,
+            to: TradeSide::from_item(item, ["to_steader", "to_possessions", "to_gp"])?,
+            created: time("created")?,
+            expires: time("expires")?,
+            status: match string("trade_status")?.as_str() {
+                "open" => TradeStatus::Open,
+                "accepted" => TradeStatus::Accepted,
+                "declined" => TradeStatus::Declined,
+                "countered" => TradeStatus::Countered(id("countered_by")?),
+                "expired" => TradeStatus::Expired,
+                _ => return Err(Custom("unknown trade status")),
+            },
+            counters: match item.get("counters") {
+                Some(_) => Some(id("counters")?),
+                None => None,
+            },
+        })
+    }
+
+    pub async fn fetch_db(db: &DynamoDbClient, id: uuid::Uuid) -> Result<Self, String> {
+        let item = db
+            .get_item(rusoto_dynamodb::GetItemInput {
+                key: TradeOffer::key(id).into_item(),
+                table_name: TABLE_NAME.to_string(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("Couldn't read trade offer {} from db: {}", id, e))?
+            .item
+            .ok_or_else(|| format!("trade offer {} not in db", id))?;
+
+        TradeOffer::from_item(&item).map_err(|e| format!("couldn't parse trade offer: {}", e))
+    }
+
+    /// Every offer the given steader has made or had made to them.
+    pub async fn fetch_all_db(db: &DynamoDbClient, steader: &str) -> Result<Vec<Self>, String> {
+        let mut offers = vec![];
+        let mut exclusive_start_key = None;
+        loop {
+            let output = db
+                .query(rusoto_dynamodb::QueryInput {
+                    table_name: TABLE_NAME.to_string(),
+                    key_condition_expression: Some("cat = :offer_cat".to_string()),
+                    filter_expression: Some(
+                        "from_steader = :steader OR to_steader = :steader".to_string(),
+                    ),
+                    expression_attribute_values: values(vec![
+                        (":offer_cat", Category::TradeOffer.into_av()),
+                        (":steader", s(steader)),
+                    ]),
+                    exclusive_start_key,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| format!("Couldn't fetch trade offers: {}", e))?;
+
+            offers.extend(output.items.unwrap_or_default().iter().filter_map(|i| {
+                match TradeOffer::from_item(i) {
+                    Ok(offer) => Some(offer),
+                    Err(e) => {
+                        println!("error parsing trade offer: {}", e);
+                        None
+                    }
+                }
+            }));
+            exclusive_start_key = output.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(offers)
+    }
+
+    /// Stores a new offer.
+    pub async fn offer_db(&self, db: &DynamoDbClient) -> Result<(), TradeError> {
+        transact(db, vec![put_new(self.item())]).await
+    }
+
+    /// Declines the offer in the database, as long as nobody else responded to it first.
+    pub async fn decline_db(
+        &mut self,
+        db: &DynamoDbClient,
+        by: &str,
+        now: SystemTime,
+    ) -> Result<(), TradeError> {
+        let mut declined = self.clone();
+        declined.decline(by, now)?;
+
+        transact(
+            db,
+            vec![rusoto_dynamodb::TransactWriteItem {
+                update: Some(declined.status.update(self.id)),
+                ..Default::default()
+            }],
+        )
+        .await?;
+        *self = declined;
+        Ok(())
+    }
+
+    /// Stores the counter offer and marks this one as countered in the database all at once,
+    /// as long as nobody else responded to this offer first.
+    pub async fn counter_db(
+        &mut self,
+        db: &DynamoDbClient,
+        by: &str,
+        gives: (Vec<Key>, u64),
+        wants: (Vec<Key>, u64),
+        now: SystemTime,
+    ) -> Result<TradeOffer, TradeError> {
+        let mut countered = self.clone();
+        let counter = countered.counter(by, gives, wants, now)?;
+
+        transact(
+            db,
+            vec![
+                rusoto_dynamodb::TransactWriteItem {
+                    update: Some(countered.status.update(self.id)),
+                    ..Default::default()
+                },
+                put_new(counter.item()),
+            ],
+        )
+        .await?;
+        *self = countered;
+        Ok(counter)
+    }
+
+    /// Marks the offer as expired in the database if it's still open past when it expires,
+    /// returning whether or not it's open now.
+    pub async fn expire_db(
+        &mut self,
+        db: &DynamoDbClient,
+        now: SystemTime,
+    ) -> Result<bool, TradeError> {
+        let mut expired = self.clone();
+        if expired.expire(now) || expired.status == self.status {
+            return Ok(expired.status == TradeStatus::Open);
+        }
+
+        transact(
+            db,
+            vec![rusoto_dynamodb::TransactWriteItem {
+                update: Some(expired.status.update(self.id)),
+                ..Default::default()
+            }],
+        )
+        .await?;
+        *self = expired;
+        Ok(false)
+    }
+}
+
+/// Stores a new offer, as long as there isn't one with the same id already.
+fn put_new(item: Item) -> rusoto_dynamodb::TransactWriteItem {
+    rusoto_dynamodb::TransactWriteItem {
+        put: Some(rusoto_dynamodb::Put {
+            table_name: TABLE_NAME.to_string(),
+            item,
+            condition_expression: Some("attribute_not_exists(id)".to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+async fn transact(
+    db: &DynamoDbClient,
+    transact_items: Vec<rusoto_dynamodb::TransactWriteItem>,
+) -> Result<(), TradeError> {
+    db.transact_write_items(rusoto_dynamodb::TransactWriteItemsInput {
+        transact_items,
+        ..Default::default()
+    })
+    .await
+    .map_err(|e| match e {
+        rusoto_core::RusotoError::Service(
+            rusoto_dynamodb::TransactWriteItemsError::TransactionCanceled(_),
+        ) => TradeError::Conflict,
+        e => TradeError::Db(e.to_string()),
+    })?;
+
+    Ok(())
+}
+
+impl Hacksteader {
+    /// Returns None if this Hacksteader doesn't have the possession with this id,
+    /// otherwise whether or not it's up for sale.
+    fn holds(&self, id: uuid::Uuid) -> Option<bool> {
+        self.inventory
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| p.sale.is_some())
+            .or_else(|| {
+                self.gotchis
+                    .iter()
+                    .find(|g| g.id == id)
+                    .map(|g| g.sale.is_some())
+            })
+    }
+
+    /// Takes a possession away from this Hacksteader to trade it,
+    /// crediting them with whatever it earned for them first if it's a gotchi.
+    fn take_possession(&mut self, id: uuid::Uuid, now: SystemTime) -> Option<Possession> {
+        if let Some(i) = self.inventory.iter().position(|p| p.id == id) {
+            return Some(self.inventory.remove(i));
+        }
+        let i = self.gotchis.iter().position(|g| g.id == id)?;
+        let mut gotchi = self.gotchis.remove(i);
+        let harvest = gotchi.harvest(now);
+        if harvest.amount > 0 {
+            self.profile
+                .wallet
+                .transact(harvest.entry())
+                .expect("credits can't fail");
+        }
+        Some(gotchi.into_possession())
+    }
+
+    /// Makes this Hacksteader the owner of a possession they've traded for.
+    fn give_possession(&mut self, mut p: Possession) {
+        p.steader = self.user_id.clone();
+        p.ownership_log.push(Owner::trader(self.user_id.clone()));
+
+        match Possessed::<possess::Gotchi>::from_possession(p.clone()) {
+            Some(g) => self.gotchis.push(g),
+            None => self.inventory.push(p),
+        }
+    }
+}
+
+#[test]
+fn trade_offers() {
+    use crate::models::test_hacksteader;
+    use crate::possess::Acquisition;
+    use crate::CONFIG;
+
+    let now = SystemTime::now();
+    let day = Duration::from_secs(60 * 60 * 24);
+    let possession = |name, owner: &str| {
+        Possession::new(
+            CONFIG.find_possession_handle(&name).unwrap(),
+            Owner::farmer(owner.to_string()),
+        )
+    };
+
+    let mut bob = test_hacksteader(vec![]);
+    let mut alice = test_hacksteader(vec![]);
+    alice.user_id = "alice".to_string();
+    alice.profile.id = "alice".to_string();
+
+    let pin = possession("Rolling Pin", "bob");
+    let gotchi = Possessed::from_possession(possession("Gempheus", "alice")).unwrap();
+    let (pin_key, gotchi_key) = (pin.key(), gotchi.clone().into_possession().key());
+    bob.inventory.push(pin);
+    alice.gotchis.push(gotchi);
+    alice
+        .profile
+        .wallet
+        .credit(30, LedgerReason::AdminGrant("admin".to_string()));
+
+    let side = |steader: &str, possessions, gp| TradeSide {
+        steader: steader.to_string(),
+        possessions,
+        gp,
+    };
+    let mut offer = TradeOffer::new(
+        side("bob", vec![pin_key], 0),
+        side("alice", vec![gotchi_key], 50),
+        now,
+        day,
+    )
+    .unwrap();
+    assert_eq!(
+        offer.decline("bob", now).unwrap_err(),
+        TradeError::NotRecipient("bob".to_string())
+    );
+
+    // alice can't afford that, so nothing changes hands
+    assert!(offer.clone().accept(now, &mut bob, &mut alice).is_err());
+    assert_eq!(bob.inventory.len(), 1);
+    assert_eq!(alice.gotchis.len(), 1);
+
+    let mut counter = offer
+        .counter("alice", (vec![gotchi_key], 20), (vec![pin_key], 0), now)
+        .unwrap();
+    assert_eq!(offer.status, TradeStatus::Countered(counter.id));
+    assert_eq!(counter.counters, Some(offer.id));
+    assert!(offer.accept(now, &mut bob, &mut alice).is_err());
+
+    counter.accept(now, &mut alice, &mut bob).unwrap();
+    assert_eq!(counter.status, TradeStatus::Accepted);
+    assert_eq!((alice.profile.wallet.gp, bob.profile.wallet.gp), (10, 20));
+    assert_eq!(alice.inventory[0].id, pin_key.id);
+    assert_eq!(bob.gotchis[0].id, gotchi_key.id);
+    assert_eq!(bob.gotchis[0].steader, "bob");
+    assert_eq!(
+        alice.inventory[0].ownership_log.last().unwrap().acquisition,
+        Acquisition::Trade
+    );
+
+    // offers can't be accepted once they've run out of time
+    let mut late =
+        TradeOffer::new(side("bob", vec![], 0), side("alice", vec![], 0), now, day).unwrap();
+    assert_eq!(
+        late.accept(now + day, &mut bob, &mut alice).unwrap_err(),
+        TradeError::NotOpen(TradeStatus::Expired)
+    );
+    assert!(TradeOffer::new(side("bob", vec![], 0), side("bob", vec![], 0), now, day).is_err());
+
+    // nothing can be traded twice in the same offer
+    assert_eq!(
+        TradeOffer::new(
+            side("bob", vec![pin_key, pin_key], 0),
+            side("alice", vec![], 0),
+            now,
+            day
+        )
+        .unwrap_err(),
+        TradeError::Duplicate(pin_key.id)
+    );
+    let mut offer = TradeOffer::new(
+        side("alice", vec![pin_key], 0),
+        side("bob", vec![], 0),
+        now,
+        day,
+    )
+    .unwrap();
+    assert_eq!(
+        offer
+            .counter(
+                "bob",
+                (vec![gotchi_key], 0),
+                (vec![pin_key, pin_key], 0),
+                now
+            )
+            .unwrap_err(),
+        TradeError::Duplicate(pin_key.id)
+    );
+    assert_eq!(offer.status, TradeStatus::Open);
+}
+
+#[test]
+fn trade_offers_in_the_db() {
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(60 * 60 * 24 * 365 * 50);
+    let day = Duration::from_secs(60 * 60 * 24);
+    let mut alices_gotchi = Possession::new(
+        crate::CONFIG.find_possession_handle(&"Gempheus").unwrap(),
+        Owner::farmer("alice".to_string()),
+    );
+    if let possess::PossessionKind::Gotchi(g) = &mut alices_gotchi.kind {
+        g.last_harvest = now;
+        g.happiness_updated = now;
+    }
+    let (pin, gotchi) = (Key::misc(uuid::Uuid::new_v4()), alices_gotchi.key());
+    let side = |steader: &str, possessions, gp| TradeSide {
+        steader: steader.to_string(),
+        possessions,
+        gp,
+    };
+
+    let mut offer = TradeOffer::new(
+        side("bob", vec![pin], 5),
+        side("alice", vec![gotchi], 25),
+        now,
+        day,
+    )
+    .unwrap();
+    assert_eq!(TradeOffer::from_item(&offer.item()).unwrap(), offer);
+    let open = offer.clone();
+    let counter = offer
+        .counter("alice", (vec![gotchi], 0), (vec![pin], 0), now)
+        .unwrap();
+    assert_eq!(TradeOffer::from_item(&offer.item()).unwrap(), offer);
+    assert_eq!(TradeOffer::from_item(&counter.item()).unwrap(), counter);
+
+    let string = |v: &AttributeValue| v.s.clone().unwrap();
+    let updates: Vec<rusoto_dynamodb::Update> = open
+        .accept_transaction(&[alices_gotchi.clone()], now)
+        .into_iter()
+        .filter_map(|i| i.update)
+        .collect();
+    for update in &updates {
+        let expressions = format!(
+            "{} {}",
+            update.update_expression,
+            update.condition_expression.clone().unwrap_or_default()
+        );
+        for placeholder in update.expression_attribute_values.as_ref().unwrap().keys() {
+            assert!(expressions.contains(placeholder.as_str()));
+        }
+    }
+
+    // the offer is only accepted if it's still open
+    let status = &updates[0];
+    assert_eq!(status.key, TradeOffer::key(open.id).into_item());
+    assert_eq!(
+        status.condition_expression,
+        Some("trade_status = :open".to_string())
+    );
+    let status_values = status.expression_attribute_values.as_ref().unwrap();
+    assert_eq!(string(&status_values[":open"]), "open");
+    assert_eq!(string(&status_values[":status"]), "accepted");
+
+    // each possession only changes hands if its owner still has it
+    for (update, (key, giver)) in updates[1..3]
+        .iter()
+        .zip(vec![(pin, "bob"), (gotchi, "alice")])
+    {
+        assert_eq!(update.key, key.into_item());
+        let values = update.expression_attribute_values.as_ref().unwrap();
+        assert_eq!(string(&values[":giver"]), giver);
+        assert!(update
+            .condition_expression
+            .as_ref()
+            .unwrap()
+            .contains("attribute_not_exists(price)"));
+    }
+
+    // alice pays the difference, but only if she can afford it
+    let (debit, credit) = (&updates[3], &updates[4]);
+    assert_eq!(debit.key, Profile::key_item("alice".to_string()));
+    assert_eq!(debit.condition_expression, Some("gp >= :debit".to_string()));
+    let debit_values = debit.expression_attribute_values.as_ref().unwrap();
+    assert_eq!(debit_values[":debit"].n, Some("20".to_string()));
+    assert_eq!(debit_values[":amount"].n, Some("20".to_string()));
+    assert_eq!(credit.key, Profile::key_item("bob".to_string()));
+    assert_eq!(credit.condition_expression, None);
+    assert_eq!(updates.len(), 5);
+
+    // gotchis are harvested for whoever gives them up, as long as nobody harvested them since
+    let hand_over = &updates[2];
+    assert!(hand_over.update_expression.contains(HARVEST_SET));
+    assert!(hand_over
+        .condition_expression
+        .as_ref()
+        .unwrap()
+        .contains(HARVEST_CONDITION));
+    let later = now + possess::gotchi::HARVEST_INTERVAL * 2;
+    let base = alices_gotchi.kind.gotchi().unwrap().base_happiness;
+    let items = open.accept_transaction(&[alices_gotchi], later);
+    let alices = items
+        .iter()
+        .filter_map(|i| i.update.as_ref())
+        .find(|u| u.key == Profile::key_item("alice".to_string()))
+        .unwrap();
+    assert_eq!(
+        alices.expression_attribute_values.as_ref().unwrap()[":amount"].n,
+        Some((20 - base * 2).to_string())
+    );
+    assert_eq!(items.iter().filter(|i| i.put.is_some()).count(), 3);
+
+    // accepting has to fit in a single transaction
+    let lots: Vec<Key> = (0..=MAX_TRADE_POSSESSIONS)
+        .map(|_| Key::gotchi(uuid::Uuid::new_v4()))
+        .collect();
+    assert_eq!(
+        TradeOffer::new(
+            side("bob", lots.clone(), 0),
+            side("alice", vec![], 0),
+            now,
+            day
+        )
+        .unwrap_err(),
+        TradeError::TooManyPossessions
+    );
+    let alices: Vec<Key> = (0..MAX_TRADE_POSSESSIONS)
+        .map(|_| Key::gotchi(uuid::Uuid::new_v4()))
+        .collect();
+    let most = TradeOffer::new(
+        side("bob", lots[1..].to_vec(), 1),
+        side("alice", alices, 0),
+        now,
+        day,
+    )
+    .unwrap();
+    // and if every gotchi earned something, each of them adds a ledger entry too
+    assert!(
+        most.accept_transaction(&[], now).len() + MAX_TRADE_POSSESSIONS * 2
+            <= MAX_TRANSACTION_ITEMS
+    );
+
+    // countering records which offer was made in response
+    let countered = offer.status.update(offer.id);
+    let countered_values = countered.expression_attribute_values.unwrap();
+    assert_eq!(string(&countered_values[":status"]), "countered");
+    assert_eq!(
+        string(&countered_values[":countered_by"]),
+        counter.id.to_string()
+    );
+    assert!(countered.update_expression.contains(":countered_by"));
+}
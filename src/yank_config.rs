@@ -1,10 +1,12 @@
 use crate::config::{
     self, Advancement, AdvancementSet, AdvancementSum, Archetype as PossessionArchetype,
-    HacksteadAdvancementSet, PlantArchetype,
+    ArchetypeKind, CountProbability, HacksteadAdvancementSet, KeepPlants, PlantAdvancement,
+    PlantArchetype, SelectivePlantAdvancement,
 };
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize, Debug)]
 /// Every good configuration file management utility has a configuration file.
@@ -20,8 +22,77 @@ struct PlantCConfig {
     include: Vec<String>,
 }
 
+/// Where yank_config should read its configuration from, where it should write
+/// the resulting config to, and whether or not it should write anything at all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YankOptions {
+    pub config_config_path: PathBuf,
+    pub output_path: PathBuf,
+    /// Validate the yanked config and print a summary, but don't write it anywhere.
+    pub dry_run: bool,
+}
+impl Default for YankOptions {
+    fn default() -> Self {
+        Self {
+            config_config_path: PathBuf::from("./config/config_config.json"),
+            output_path: PathBuf::from("./config/content.json"),
+            dry_run: false,
+        }
+    }
+}
+impl YankOptions {
+    pub const USAGE: &'static str = concat!(
+        "usage: yank_config [--config-config <path>] [--output <path>] [--dry-run]\n",
+        "  --config-config <path>  where to read the config config from ",
+        "(default ./config/config_config.json)\n",
+        "  --output <path>         where to write the yanked config ",
+        "(default ./config/content.json)\n",
+        "  --dry-run               validate and summarize the config without writing it",
+    );
+
+    /// Parses command line arguments, not including the name of the binary.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, YankError> {
+        let mut opts = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config-config" | "-c" => {
+                    opts.config_config_path = args
+                        .next()
+                        .ok_or(YankError::ArgumentError(format!("{} needs a path", arg)))?
+                        .into();
+                }
+                "--output" | "-o" => {
+                    opts.output_path = args
+                        .next()
+                        .ok_or(YankError::ArgumentError(format!("{} needs a path", arg)))?
+                        .into();
+                }
+                "--dry-run" => opts.dry_run = true,
+                other => {
+                    return Err(YankError::ArgumentError(format!(
+                        "unknown argument {:?}",
+                        other
+                    )))
+                }
+            }
+        }
+
+        Ok(opts)
+    }
+}
+
 #[derive(Debug)]
 pub enum YankError {
+    /// Contains: Invalid argument description
+    ArgumentError(String),
+    /// Contains: Environment Variable Name, Error
+    EnvError(&'static str, std::env::VarError),
+    /// Contains: Path to the config config, Error
+    ReadConfigConfigError(PathBuf, std::io::Error),
+    /// Contains: Path to the config config, Error
+    ParseConfigConfigError(PathBuf, serde_json::Error),
     /// Contains: Message, Error
     RequestError(&'static str, reqwest::Error),
     /// Contains: Sheet Name, Error
@@ -35,6 +106,20 @@ impl fmt::Display for YankError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use YankError::*;
         match self {
+            ArgumentError(e) => write!(f, "Argument Error: {}\n{}", e, YankOptions::USAGE),
+            EnvError(var, e) => write!(f, "Environment Error({}): {}", var, e),
+            ReadConfigConfigError(path, e) => write!(
+                f,
+                "Error reading the config config at {}: {}",
+                path.display(),
+                e
+            ),
+            ParseConfigConfigError(path, e) => write!(
+                f,
+                "Error parsing the config config at {}: {}",
+                path.display(),
+                e
+            ),
             RequestError(msg, e) => write!(f, "Request Error({}): {}", msg, e),
             SheetError(sheet_name, e) => write!(f, "Error parsing \"{}\" sheet: {}", sheet_name, e),
             ArchetypeNameError(e) => write!(f, "Archetype Name Error: {}", e),
@@ -54,6 +139,12 @@ pub enum SheetError {
     CellFloatParsingError(&'static str, std::num::ParseFloatError, usize),
     /// Contains: Column Header, Integer Parsing Error, row
     CellIntParsingError(&'static str, std::num::ParseIntError, usize),
+    /// Contains: Column Header, Archetype Name, row
+    UnknownArchetype(&'static str, String, usize),
+    /// Contains: Column Header, Archetype Name, Expected Archetype Kind, row
+    WrongArchetypeKind(&'static str, String, &'static str, usize),
+    /// Contains: Keep Plants Cell, row
+    InvalidKeepPlants(String, usize),
     /// Contains a description of that which is missing.
     Missing(&'static str),
     /// unspecified JSON error
@@ -94,27 +185,43 @@ impl fmt::Display for SheetError {
                 "Invalid integer number in \"{}\" cell on row {}: {}",
                 cell_desc, row, int_err,
             ),
+            UnknownArchetype(cell_desc, name, row) => write!(
+                f,
+                "unknown archetype {:?} in \"{}\" cell on row {}",
+                name, cell_desc, row,
+            ),
+            WrongArchetypeKind(cell_desc, name, expected, row) => write!(
+                f,
+                "archetype {:?} in \"{}\" cell on row {} isn't a {}",
+                name, cell_desc, row, expected,
+            ),
+            InvalidKeepPlants(cell, row) => write!(
+                f,
+                concat!(
+                    "Invalid \"Keep Plants\" cell on row {}: {:?}\n",
+                    "expected \"All\", \"Only: <plant>, <plant>\" or \"Not: <plant>, <plant>\"",
+                ),
+                row, cell,
+            ),
             Missing(what) => write!(f, "missing {}", what),
             JsonError(msg, e) => write!(f, "json error: {}: {}", msg, e),
         }
     }
 }
 
-lazy_static::lazy_static! {
-    static ref KEY: String = std::env::var("GOOGLE_CONFIG_KEY").unwrap().to_string();
-
-    // Every good configuration file management utility has a configuration file.
-    static ref C_CONFIG: CConfig = {
-        const PATH: &'static str = concat!(
-           "./config/config_config.json"
-        );
+/// The API key used to read from Google Sheets.
+fn google_key() -> Result<String, YankError> {
+    const VAR: &'static str = "GOOGLE_CONFIG_KEY";
+    std::env::var(VAR).map_err(|e| YankError::EnvError(VAR, e))
+}
 
-        serde_json::from_str(
-                &std::fs::read_to_string(PATH)
-                    .unwrap_or_else(|e| panic!("opening {}: {}", PATH, e))
-            )
-            .unwrap_or_else(|e| panic!("parsing {}: {}", PATH, e))
-    };
+// Every good configuration file management utility has a configuration file.
+fn load_c_config(path: &Path) -> Result<CConfig, YankError> {
+    serde_json::from_str(
+        &std::fs::read_to_string(path)
+            .map_err(|e| YankError::ReadConfigConfigError(path.to_path_buf(), e))?,
+    )
+    .map_err(|e| YankError::ParseConfigConfigError(path.to_path_buf(), e))
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -123,43 +230,75 @@ struct Sheet {
     values: Vec<Vec<String>>,
     name: String,
 }
+/// A row from a sheet which adds something onto an archetype from the "Items" sheet.
+struct ArchetypeAddition<T> {
+    /// The name of the possession archetype this row adds onto
+    archetype: String,
+    value: T,
+    /// The row this addition came from, for error messages
+    row: usize,
+}
+
+/// Pops the trailing Xp, Title, Description, Achiever Title, Art, Kind and Value
+/// cells off of a row, converting them into a single advancement.
+fn pop_advancement<S: AdvancementSum>(
+    v: &mut Vec<String>,
+    row: usize,
+) -> Result<Advancement<S>, SheetError> {
+    let value = v.pop().ok_or(MissingCell("Value", row))?;
+    let kind = v.pop().ok_or(MissingCell("Kind", row))?;
+    let kind_json = format!("{{ \"{}\": {} }}", kind, value);
+
+    Ok(Advancement {
+        kind: serde_json::from_str(&kind_json)
+            .map_err(|e| CellJsonError("value", e, kind_json, row))?,
+        art: v.pop().ok_or(MissingCell("Art", row))?,
+        achiever_title: v.pop().ok_or(MissingCell("Achiever Title", row))?,
+        description: v.pop().ok_or(MissingCell("Description", row))?,
+        title: v.pop().ok_or(MissingCell("Title", row))?,
+        xp: v
+            .pop()
+            .ok_or(MissingCell("Xp", row))?
+            .parse()
+            .map_err(|e| CellIntParsingError("xp", e, row))?,
+    })
+}
+
+/// Parses "All", "Only: Bractus, Coffea Cyl" or "Not: Bractus" into KeepPlants.
+fn parse_keep_plants(cell: &str, row: usize) -> Result<KeepPlants<String>, SheetError> {
+    let names = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect()
+    };
+
+    let cell = cell.trim();
+    if cell == "All" {
+        return Ok(KeepPlants::All);
+    }
+
+    let mut sections = cell.splitn(2, ':');
+    match (sections.next().map(|s| s.trim()), sections.next()) {
+        (Some("Only"), Some(list)) => Ok(KeepPlants::Only(names(list))),
+        (Some("Not"), Some(list)) => Ok(KeepPlants::Not(names(list))),
+        _ => Err(InvalidKeepPlants(cell.to_string(), row)),
+    }
+}
+
 impl Sheet {
     /// turns a sheet into a list of advancements
     fn to_advancements<S: AdvancementSum>(
         self,
         row_offset: usize,
     ) -> Result<AdvancementSet<S>, SheetError> {
-        // convert a single advancement
-        fn to_advancement<S: AdvancementSum>(
-            mut v: Vec<String>,
-            row: usize,
-        ) -> Result<Advancement<S>, SheetError> {
-            let value = v.pop().ok_or(MissingCell("Value", row))?;
-            let kind = v.pop().ok_or(MissingCell("Kind", row))?;
-            let kind_json = format!("{{ \"{}\": {} }}", kind, value);
-
-            Ok(Advancement {
-                kind: serde_json::from_str(&kind_json)
-                    .map_err(|e| CellJsonError("value", e, kind_json, row))?,
-                art: v.pop().ok_or(MissingCell("Art", row))?,
-                achiever_title: v.pop().ok_or(MissingCell("Achiever Title", row))?,
-                description: v.pop().ok_or(MissingCell("Description", row))?,
-                title: v.pop().ok_or(MissingCell("Title", row))?,
-                xp: v
-                    .pop()
-                    .ok_or(MissingCell("Xp", row))?
-                    .parse()
-                    .map_err(|e| CellIntParsingError("xp", e, row))?,
-            })
-        }
-
         let mut advancements = self
             .values
             .into_iter()
             .enumerate()
             .skip(row_offset)
             // adding one because google sheets starts at 1 not 0
-            .map(|(i, x)| to_advancement(x, i + row_offset + 1))
+            .map(|(i, mut x)| pop_advancement(&mut x, i + row_offset + 1))
             .collect::<Result<Vec<_>, _>>()?;
         let base_index = advancements
             .iter()
@@ -202,6 +341,147 @@ impl Sheet {
             .collect()
     }
 
+    /// turns a sheet with a column of Slack IDs into a list of special users
+    fn to_special_users(self, row_offset: usize) -> Result<Vec<String>, SheetError> {
+        self.values
+            .into_iter()
+            .enumerate()
+            .skip(row_offset)
+            .map(|(i, v)| {
+                v.into_iter()
+                    .next()
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    // adding one because google sheets starts at 1 not 0
+                    .ok_or(MissingCell("Slack ID", i + 1))
+            })
+            .collect()
+    }
+
+    /// turns a sheet with Egg, Chance, Min, Max and Hatches Into columns into
+    /// rows to add onto the hatch tables of eggs.
+    fn to_hatch_table_rows(
+        self,
+        row_offset: usize,
+    ) -> Result<Vec<ArchetypeAddition<(CountProbability, String)>>, SheetError> {
+        fn to_hatch_table_row(
+            mut v: Vec<String>,
+            row: usize,
+        ) -> Result<ArchetypeAddition<(CountProbability, String)>, SheetError> {
+            let hatches_into = v.pop().ok_or(MissingCell("Hatches Into", row))?;
+
+            let mut float = |column: &'static str| -> Result<f32, SheetError> {
+                v.pop()
+                    .ok_or(MissingCell(column, row))?
+                    .trim()
+                    .parse()
+                    .map_err(|e| CellFloatParsingError(column, e, row))
+            };
+            let max = float("Max")?;
+            let min = float("Min")?;
+            let chance = float("Chance")?;
+
+            Ok(ArchetypeAddition {
+                archetype: v.pop().ok_or(MissingCell("Egg", row))?,
+                value: (CountProbability(chance, (min, max)), hatches_into),
+                row,
+            })
+        }
+
+        self.values
+            .into_iter()
+            .enumerate()
+            .skip(row_offset)
+            // adding one because google sheets starts at 1 not 0
+            .map(|(i, x)| to_hatch_table_row(x, i + 1))
+            .collect()
+    }
+
+    /// turns a sheet with Owner and Keep Plants columns followed by the usual
+    /// advancement columns into plant effects for gotchis and keepsakes.
+    fn to_plant_effects(
+        self,
+        row_offset: usize,
+    ) -> Result<Vec<ArchetypeAddition<SelectivePlantAdvancement>>, SheetError> {
+        fn to_plant_effect(
+            mut v: Vec<String>,
+            row: usize,
+        ) -> Result<ArchetypeAddition<SelectivePlantAdvancement>, SheetError> {
+            let advancement: PlantAdvancement = pop_advancement(&mut v, row)?;
+            let keep_plants = v.pop().ok_or(MissingCell("Keep Plants", row))?;
+
+            Ok(ArchetypeAddition {
+                value: SelectivePlantAdvancement {
+                    keep_plants: parse_keep_plants(&keep_plants, row)?,
+                    advancement,
+                },
+                archetype: v.pop().ok_or(MissingCell("Owner", row))?,
+                row,
+            })
+        }
+
+        self.values
+            .into_iter()
+            .enumerate()
+            .skip(row_offset)
+            // adding one because google sheets starts at 1 not 0
+            .map(|(i, x)| to_plant_effect(x, i + 1))
+            .collect()
+    }
+
+    /// turns a sheet with Gotchi, Food and Happiness columns into
+    /// foods to add onto what gotchis can be fed.
+    fn to_gotchi_foods(
+        self,
+        row_offset: usize,
+    ) -> Result<Vec<ArchetypeAddition<(String, u64)>>, SheetError> {
+        fn to_gotchi_food(
+            mut v: Vec<String>,
+            row: usize,
+        ) -> Result<ArchetypeAddition<(String, u64)>, SheetError> {
+            let happiness = v
+                .pop()
+                .ok_or(MissingCell("Happiness", row))?
+                .trim()
+                .parse()
+                .map_err(|e| CellIntParsingError("happiness", e, row))?;
+            let food = v.pop().ok_or(MissingCell("Food", row))?;
+
+            Ok(ArchetypeAddition {
+                archetype: v.pop().ok_or(MissingCell("Gotchi", row))?,
+                value: (food.trim().to_string(), happiness),
+                row,
+            })
+        }
+
+        self.values
+            .into_iter()
+            .enumerate()
+            .skip(row_offset)
+            // adding one because google sheets starts at 1 not 0
+            .map(|(i, x)| to_gotchi_food(x, i + 1))
+            .collect()
+    }
+
+    /// turns a sheet with a column of gotchi names into a list of
+    /// gotchis to give to new hacksteaders.
+    fn to_welcome_gifts(self, row_offset: usize) -> Result<Vec<ArchetypeAddition<()>>, SheetError> {
+        self.values
+            .into_iter()
+            .enumerate()
+            .skip(row_offset)
+            .map(|(i, v)| {
+                // adding one because google sheets starts at 1 not 0
+                let row = i + 1;
+                Ok(ArchetypeAddition {
+                    archetype: v.into_iter().next().ok_or(MissingCell("Gotchi", row))?,
+                    value: (),
+                    row,
+                })
+            })
+            .collect()
+    }
+
     fn to_plant_archetype(self) -> Result<PlantArchetype, SheetError> {
         if self.values.is_empty() {
             return Err(Missing("entire sheet"));
@@ -240,14 +520,126 @@ impl Sheet {
     }
 }
 
-async fn yank_sheet(client: &Client, id: &str, name: String) -> Result<Sheet, YankError> {
+/// Turns SheetErrors into YankErrors which record the name of the sheet they came from.
+fn in_sheet(sheet_name: &'static str) -> impl Fn(SheetError) -> YankError {
+    move |e| YankError::SheetError(sheet_name.to_string(), e)
+}
+
+fn find_addition_archetype<'a, T>(
+    items: &'a mut [PossessionArchetype],
+    addition: &ArchetypeAddition<T>,
+    column: &'static str,
+) -> Result<&'a mut ArchetypeKind, SheetError> {
+    items
+        .iter_mut()
+        .find(|a| a.name == addition.archetype)
+        .map(|a| &mut a.kind)
+        .ok_or_else(|| UnknownArchetype(column, addition.archetype.clone(), addition.row))
+}
+
+/// Tacks the rows from the "Hatch Tables", "Plant Effects", "Welcome Gifts" and
+/// "Gotchi Foods" sheets onto the archetypes yanked from the "Items" sheet.
+fn add_sheet_extras(
+    items: &mut [PossessionArchetype],
+    hatch_table_rows: Vec<ArchetypeAddition<(CountProbability, String)>>,
+    plant_effects: Vec<ArchetypeAddition<SelectivePlantAdvancement>>,
+    welcome_gifts: Vec<ArchetypeAddition<()>>,
+    gotchi_foods: Vec<ArchetypeAddition<(String, u64)>>,
+) -> Result<(), YankError> {
+    for addition in hatch_table_rows {
+        match find_addition_archetype(items, &addition, "Egg").map_err(in_sheet("Hatch Tables"))? {
+            ArchetypeKind::Gotchi(g) => g
+                .hatch_table
+                .get_or_insert_with(Vec::new)
+                .push(addition.value),
+            _ => {
+                return Err(in_sheet("Hatch Tables")(WrongArchetypeKind(
+                    "Egg",
+                    addition.archetype,
+                    "gotchi",
+                    addition.row,
+                )))
+            }
+        }
+    }
+
+    for addition in plant_effects {
+        match find_addition_archetype(items, &addition, "Owner")
+            .map_err(in_sheet("Plant Effects"))?
+        {
+            ArchetypeKind::Gotchi(g) => g.plant_effects.push(addition.value),
+            ArchetypeKind::Keepsake(k) => k.plant_effects.push(addition.value),
+            ArchetypeKind::Seed(_) => {
+                return Err(in_sheet("Plant Effects")(WrongArchetypeKind(
+                    "Owner",
+                    addition.archetype,
+                    "gotchi or keepsake",
+                    addition.row,
+                )))
+            }
+        }
+    }
+
+    for addition in welcome_gifts {
+        match find_addition_archetype(items, &addition, "Gotchi")
+            .map_err(in_sheet("Welcome Gifts"))?
+        {
+            ArchetypeKind::Gotchi(g) => g.welcome_gift = true,
+            _ => {
+                return Err(in_sheet("Welcome Gifts")(WrongArchetypeKind(
+                    "Gotchi",
+                    addition.archetype,
+                    "gotchi",
+                    addition.row,
+                )))
+            }
+        }
+    }
+
+    for addition in gotchi_foods {
+        match find_addition_archetype(items, &addition, "Gotchi")
+            .map_err(in_sheet("Gotchi Foods"))?
+        {
+            ArchetypeKind::Gotchi(g) => g.foods.push(addition.value),
+            _ => {
+                return Err(in_sheet("Gotchi Foods")(WrongArchetypeKind(
+                    "Gotchi",
+                    addition.archetype,
+                    "gotchi",
+                    addition.row,
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Land Grants are listed as requiring xp in the "Items" sheet, but they're meant to
+/// grant land even to Hacksteaders who haven't earned it, so that's fixed up here.
+fn correct_sheet_mistakes(items: &mut [PossessionArchetype]) {
+    for item in items.iter_mut().filter(|a| a.name == "Land Grant") {
+        if let ArchetypeKind::Keepsake(k) = &mut item.kind {
+            if let Some(unlock) = k.unlocks_land.as_mut() {
+                unlock.requires_xp = false;
+            }
+        }
+    }
+}
+
+async fn yank_sheet(
+    client: &Client,
+    key: &str,
+    id: &str,
+    name: String,
+) -> Result<Sheet, YankError> {
     let v: serde_json::Value = client
         .get(&format!(
             concat!(
                 "https://sheets.googleapis.com/v4/spreadsheets/{}/",
                 "values/{}?key={}",
             ),
-            id, name, *KEY
+            id, name, key
         ))
         .send()
         .await
@@ -284,6 +676,7 @@ async fn yank_sheet_not_empty() {
     let client = reqwest::Client::new();
     let s = yank_sheet(
         &client,
+        &google_key().unwrap_or_else(|e| panic!("{}", e)),
         "129av9xlxkby72vkYOJrKHN1ybEM1EGY_YHGsFoSgGwo",
         "bractus".to_string(),
     )
@@ -296,7 +689,48 @@ async fn yank_sheet_not_empty() {
 fn load_config() {
     dotenv::dotenv().ok();
 
-    println!("{:#?}", *C_CONFIG);
+    let c_config = load_c_config(&YankOptions::default().config_config_path)
+        .unwrap_or_else(|e| panic!("{}", e));
+    println!("{:#?}", c_config);
+}
+
+#[test]
+fn yank_options_from_args() {
+    let args = |a: &[&str]| YankOptions::from_args(a.iter().map(|x| x.to_string()));
+
+    assert_eq!(args(&[]).unwrap(), YankOptions::default());
+    assert_eq!(
+        args(&[
+            "--output",
+            "/tmp/content.json",
+            "--dry-run",
+            "-c",
+            "cc.json"
+        ])
+        .unwrap(),
+        YankOptions {
+            config_config_path: "cc.json".into(),
+            output_path: "/tmp/content.json".into(),
+            dry_run: true,
+        }
+    );
+    assert!(matches!(
+        args(&["--output"]),
+        Err(YankError::ArgumentError(_))
+    ));
+    assert!(matches!(
+        args(&["--frobnicate"]),
+        Err(YankError::ArgumentError(_))
+    ));
+}
+
+#[test]
+fn missing_config_config() {
+    let path = Path::new("./config/this_config_config_does_not_exist.json");
+    assert!(matches!(
+        load_c_config(path),
+        Err(YankError::ReadConfigConfigError(..))
+    ));
 }
 
 #[test]
@@ -362,26 +796,270 @@ fn sheet_to_advancement() {
     );
 }
 
+#[test]
+fn sheet_to_hatch_table_and_plant_effects() {
+    use config::{GotchiArchetype, KeepsakeArchetype, PlantAdvancementKind};
+
+    fn sheet(name: &str, values: Vec<Vec<&str>>) -> Sheet {
+        Sheet {
+            name: name.to_string(),
+            values: values
+                .into_iter()
+                .map(|v| v.into_iter().map(|x| x.to_string()).collect())
+                .collect(),
+        }
+    }
+    fn archetype(name: &str, kind: ArchetypeKind) -> PossessionArchetype {
+        PossessionArchetype {
+            name: name.to_string(),
+            description: String::new(),
+            kind,
+        }
+    }
+
+    let mut items = vec![
+        archetype(
+            "Cyl Egg",
+            ArchetypeKind::Gotchi(GotchiArchetype {
+                base_happiness: 0,
+                foods: vec![],
+                happiness_decay: 1,
+                plant_effects: vec![],
+                hatch_table: None,
+                welcome_gift: false,
+            }),
+        ),
+        archetype(
+            "Cyl Wand",
+            ArchetypeKind::Keepsake(KeepsakeArchetype {
+                unlocks_land: None,
+                plant_effects: vec![],
+                item_application: None,
+            }),
+        ),
+    ];
+
+    let hatch_table_rows = sheet(
+        "Hatch Tables",
+        vec![
+            vec!["Egg", "Chance", "Min", "Max", "Hatches Into"],
+            vec!["Cyl Egg", "0.5", "1", "2", "Cyl Crystal"],
+        ],
+    )
+    .to_hatch_table_rows(1)
+    .unwrap();
+    let plant_effects = sheet(
+        "Plant Effects",
+        vec![
+            vec!["Owner", "Keep Plants", "Xp", "Title", "..."],
+            vec![
+                "Cyl Wand",
+                "Only: Coffea Cyl",
+                "0",
+                "Cyl Wand Bonus",
+                "Desc",
+                "Cyl Mage",
+                "Art",
+                "YieldSpeedMultiplier",
+                "1.35",
+            ],
+        ],
+    )
+    .to_plant_effects(1)
+    .unwrap();
+    let welcome_gifts = sheet("Welcome Gifts", vec![vec!["Gotchi"], vec!["Cyl Egg"]])
+        .to_welcome_gifts(1)
+        .unwrap();
+    let gotchi_foods = sheet(
+        "Gotchi Foods",
+        vec![
+            vec!["Gotchi", "Food", "Happiness"],
+            vec!["Cyl Egg", "Cyl Wand", "15"],
+        ],
+    )
+    .to_gotchi_foods(1)
+    .unwrap();
+
+    add_sheet_extras(
+        &mut items,
+        hatch_table_rows,
+        plant_effects,
+        welcome_gifts,
+        gotchi_foods,
+    )
+    .unwrap();
+
+    let egg = items[0].kind.gotchi().unwrap();
+    assert!(egg.welcome_gift);
+    assert_eq!(egg.foods, vec![("Cyl Wand".to_string(), 15)]);
+    assert_eq!(
+        egg.hatch_table,
+        Some(vec![(
+            CountProbability(0.5, (1.0, 2.0)),
+            "Cyl Crystal".to_string()
+        )])
+    );
+
+    let wand = items[1].kind.keepsake().unwrap();
+    assert_eq!(wand.plant_effects.len(), 1);
+    assert!(wand.plant_effects[0]
+        .keep_plants
+        .allows(&"Coffea Cyl".to_string()));
+    assert_eq!(
+        wand.plant_effects[0].advancement.kind,
+        PlantAdvancementKind::YieldSpeedMultiplier(1.35)
+    );
+
+    // keepsakes can't hatch
+    let bad_row = sheet(
+        "Hatch Tables",
+        vec![vec!["Cyl Wand", "0.5", "1", "2", "Cyl Crystal"]],
+    )
+    .to_hatch_table_rows(0)
+    .unwrap();
+    assert!(matches!(
+        add_sheet_extras(&mut items, bad_row, vec![], vec![], vec![]),
+        Err(YankError::SheetError(_, WrongArchetypeKind("Egg", _, _, 1)))
+    ));
+
+    // and only gotchis eat
+    let bad_row = sheet("Gotchi Foods", vec![vec!["Cyl Wand", "Cyl Egg", "5"]])
+        .to_gotchi_foods(0)
+        .unwrap();
+    assert!(matches!(
+        add_sheet_extras(&mut items, vec![], vec![], vec![], bad_row),
+        Err(YankError::SheetError(
+            _,
+            WrongArchetypeKind("Gotchi", _, _, 1)
+        ))
+    ));
+}
+
+#[test]
+fn land_grants_never_require_xp() {
+    use config::{KeepsakeArchetype, LandUnlock};
+
+    let deed = |name: &str| PossessionArchetype {
+        name: name.to_string(),
+        description: String::new(),
+        kind: ArchetypeKind::Keepsake(KeepsakeArchetype {
+            unlocks_land: Some(LandUnlock { requires_xp: true }),
+            plant_effects: vec![],
+            item_application: None,
+        }),
+    };
+    let mut items = vec![deed("Land Deed"), deed("Land Grant")];
+    correct_sheet_mistakes(&mut items);
+
+    let requires_xp: Vec<bool> = items
+        .iter()
+        .map(|a| match &a.kind {
+            ArchetypeKind::Keepsake(k) => k.unlocks_land.as_ref().unwrap().requires_xp,
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(requires_xp, vec![true, false]);
+}
+
+#[test]
+fn sheet_to_special_users() {
+    let sheet = Sheet {
+        name: "Special Users".to_string(),
+        values: vec![
+            vec!["Slack ID".to_string()],
+            vec!["UTZBECLA2".to_string(), "a note".to_string()],
+            vec![],
+        ],
+    };
+
+    assert!(matches!(
+        sheet.to_special_users(1),
+        Err(MissingCell("Slack ID", 3))
+    ));
+}
+
+#[test]
+fn keep_plants_cell() {
+    assert!(matches!(parse_keep_plants("All", 1), Ok(KeepPlants::All)));
+    assert!(matches!(
+        parse_keep_plants("Not: Bractus, Coffea Cyl", 1),
+        Ok(KeepPlants::Not(ref these)) if these.len() == 2
+    ));
+    assert!(matches!(
+        parse_keep_plants("Some: Bractus", 2),
+        Err(InvalidKeepPlants(_, 2))
+    ));
+}
+
 #[tokio::test]
 async fn yank_config_full() {
-    yank_config()
+    dotenv::dotenv().ok();
+
+    yank_config(&YankOptions::default())
         .await
-        .unwrap_or_else(|e| panic!("couldn't yank: {}", e))
+        .unwrap_or_else(|e| panic!("couldn't yank: {}", e));
+}
+
+/// A short, human readable overview of a freshly yanked config.
+pub struct ConfigSummary<'a>(pub &'a config::Config);
+impl fmt::Display for ConfigSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let config = self.0;
+
+        writeln!(f, "{} special users", config.special_users.len())?;
+        writeln!(
+            f,
+            "{} hackstead advancements",
+            config.profile_archetype.advancements.all().count()
+        )?;
+        writeln!(f, "{} plant archetypes:", config.plant_archetypes.len())?;
+        for plant in config.plant_archetypes.iter() {
+            writeln!(
+                f,
+                "  {} ({} advancements)",
+                plant.name,
+                plant.advancements.all().count()
+            )?;
+        }
+        write!(
+            f,
+            "{} possession archetypes",
+            config.possession_archetypes.len()
+        )
+    }
 }
 
-pub async fn yank_config() -> Result<(), YankError> {
+/// Yanks the config from Google Sheets, validates it, and unless `opts.dry_run`
+/// is set, writes it to `opts.output_path`.
+pub async fn yank_config(opts: &YankOptions) -> Result<config::Config, YankError> {
     use futures::stream::{self, StreamExt, TryStreamExt};
 
+    let c_config = load_c_config(&opts.config_config_path)?;
+    let key = google_key()?;
     let client = reqwest::Client::new();
 
-    let (mut plants, hackstead_advancements, items): (
+    let (
+        mut plants,
+        hackstead_advancements,
+        mut items,
+        special_users,
+        hatch_table_rows,
+        plant_effects,
+        welcome_gifts,
+        gotchi_foods,
+    ): (
         Vec<PlantArchetype>,
         HacksteadAdvancementSet,
         Vec<PossessionArchetype>,
+        Vec<String>,
+        _,
+        _,
+        _,
+        _,
     ) = futures::try_join!(
-        stream::iter(C_CONFIG.plants.include.clone())
+        stream::iter(c_config.plants.include.clone())
             .map(|plant_name| async {
-                yank_sheet(&client, &C_CONFIG.plants.sheet_id, plant_name.clone())
+                yank_sheet(&client, &key, &c_config.plants.sheet_id, plant_name.clone())
                     .await?
                     .to_plant_archetype()
                     .map_err(|e| YankError::SheetError(plant_name, e))
@@ -391,7 +1069,8 @@ pub async fn yank_config() -> Result<(), YankError> {
         async {
             yank_sheet(
                 &client,
-                &C_CONFIG.hackstead_advancements_sheet_id,
+                &key,
+                &c_config.hackstead_advancements_sheet_id,
                 "Hackstead Advancements".to_string(),
             )
             .await
@@ -401,17 +1080,76 @@ pub async fn yank_config() -> Result<(), YankError> {
             })
         },
         async {
-            yank_sheet(&client, &C_CONFIG.items_sheet_id, "Items".to_string())
+            yank_sheet(&client, &key, &c_config.items_sheet_id, "Items".to_string())
                 .await
                 .and_then(|s| {
                     s.to_possession_archetypes(1)
                         .map_err(|e| YankError::SheetError("Items".to_string(), e))
                 })
+        },
+        async {
+            yank_sheet(
+                &client,
+                &key,
+                &c_config.items_sheet_id,
+                "Special Users".to_string(),
+            )
+            .await
+            .and_then(|s| s.to_special_users(1).map_err(in_sheet("Special Users")))
+        },
+        async {
+            yank_sheet(
+                &client,
+                &key,
+                &c_config.items_sheet_id,
+                "Hatch Tables".to_string(),
+            )
+            .await
+            .and_then(|s| s.to_hatch_table_rows(1).map_err(in_sheet("Hatch Tables")))
+        },
+        async {
+            yank_sheet(
+                &client,
+                &key,
+                &c_config.items_sheet_id,
+                "Plant Effects".to_string(),
+            )
+            .await
+            .and_then(|s| s.to_plant_effects(1).map_err(in_sheet("Plant Effects")))
+        },
+        async {
+            yank_sheet(
+                &client,
+                &key,
+                &c_config.items_sheet_id,
+                "Welcome Gifts".to_string(),
+            )
+            .await
+            .and_then(|s| s.to_welcome_gifts(1).map_err(in_sheet("Welcome Gifts")))
+        },
+        async {
+            yank_sheet(
+                &client,
+                &key,
+                &c_config.items_sheet_id,
+                "Gotchi Foods".to_string(),
+            )
+            .await
+            .and_then(|s| s.to_gotchi_foods(1).map_err(in_sheet("Gotchi Foods")))
         }
     )?;
 
+    add_sheet_extras(
+        &mut items,
+        hatch_table_rows,
+        plant_effects,
+        welcome_gifts,
+        gotchi_foods,
+    )?;
+    correct_sheet_mistakes(&mut items);
+
     plants.sort_by_key(|p| {
-        C_CONFIG
+        c_config
             .plants
             .include
             .iter()
@@ -420,19 +1158,25 @@ pub async fn yank_config() -> Result<(), YankError> {
     });
 
     let config = config::Config {
-        special_users: vec![],
+        special_users,
         profile_archetype: config::ProfileArchetype {
             advancements: hackstead_advancements,
         },
         plant_archetypes: plants,
         possession_archetypes: items,
+        markets: vec![],
     };
 
     config::check_archetype_name_matches(&config).map_err(|e| YankError::ArchetypeNameError(e))?;
 
-    std::fs::write(
-        std::path::Path::new("./config/content.json"),
-        &serde_json::to_string_pretty(&config).map_err(|e| YankError::SerializeConfigError(e))?,
-    )
-    .map_err(|e| YankError::WriteConfigError(e))
+    if !opts.dry_run {
+        std::fs::write(
+            &opts.output_path,
+            &serde_json::to_string_pretty(&config)
+                .map_err(|e| YankError::SerializeConfigError(e))?,
+        )
+        .map_err(|e| YankError::WriteConfigError(e))?;
+    }
+
+    Ok(config)
 }